@@ -0,0 +1,201 @@
+use bevy::{ecs::system::Command, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  blend::{rig_bones, sample_curve},
+  lod::is_evaluated,
+  AnimationController, AnimationControllerInput, AnimationLod, Animator, AnimatorTarget,
+};
+
+/// A clip layered on top of the pose produced by the controller, as the difference between the
+/// clip and its pose at `reference_time`.
+#[derive(Clone)]
+pub struct AdditiveLayer {
+  pub clip: Handle<AnimationClip>,
+  pub weight: AdditiveWeight,
+  pub reference_time: f32,
+  pub speed: f32,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum AdditiveWeight {
+  Constant(f32),
+  /// Read from the animator's [`AnimationControllerInput`]
+  Parameter(String),
+}
+
+impl AdditiveWeight {
+  fn resolve(&self, input: &AnimationControllerInput) -> f32 {
+    match self {
+      AdditiveWeight::Constant(weight) => *weight,
+      AdditiveWeight::Parameter(name) => input.get_parameter(name),
+    }
+  }
+}
+
+/// Additive playback state of an animated entity.
+#[derive(Component, Default)]
+pub struct AdditiveLayers {
+  layer_times: Vec<f32>,
+  one_shots: Vec<OneShot>,
+  rig: Option<Entity>,
+  bones: Vec<(Entity, EntityPath)>,
+}
+
+struct OneShot {
+  clip: Handle<AnimationClip>,
+  weight: f32,
+  time: f32,
+}
+
+impl AdditiveLayers {
+  /// Plays `clip` once on top of everything else, relative to its first frame.
+  pub fn play_additive(&mut self, clip: Handle<AnimationClip>, weight: f32) {
+    self.one_shots.push(OneShot {
+      clip,
+      weight,
+      time: 0.0,
+    });
+  }
+}
+
+pub struct PlayAdditive {
+  pub entity: Entity,
+  pub clip: Handle<AnimationClip>,
+  pub weight: f32,
+}
+
+impl Command for PlayAdditive {
+  fn apply(self, world: &mut World) {
+    let Some(mut layers) = world.get_mut::<AdditiveLayers>(self.entity) else {
+      warn!(
+        "Cannot play additive clip, {:?} is not animated",
+        self.entity
+      );
+      return;
+    };
+    layers.play_additive(self.clip, self.weight);
+  }
+}
+
+pub trait AdditiveCommandsExt {
+  /// Fire-and-forget additive clip on an animated entity, e.g. a hit flinch. Does not interrupt
+  /// the controller.
+  fn play_additive(&mut self, entity: Entity, clip: Handle<AnimationClip>, weight: f32);
+}
+
+impl AdditiveCommandsExt for Commands<'_, '_> {
+  fn play_additive(&mut self, entity: Entity, clip: Handle<AnimationClip>, weight: f32) {
+    self.add(PlayAdditive {
+      entity,
+      clip,
+      weight,
+    });
+  }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_additive_layers<T: AnimationController>(
+  controllers: Res<Assets<T>>,
+  clips: Res<Assets<AnimationClip>>,
+  time: Res<Time>,
+  mut qry: Query<(
+    Entity,
+    &Animator<T>,
+    &AnimatorTarget,
+    &AnimationControllerInput,
+    &mut AdditiveLayers,
+  )>,
+  lods: Query<&AnimationLod>,
+  mut transforms: Query<&mut Transform>,
+  children: Query<&Children>,
+  names: Query<&Name>,
+) {
+  let delta = time.delta_seconds();
+  for (entity, animator, target, input, mut layers) in qry.iter_mut() {
+    let layers = &mut *layers;
+    let evaluate = is_evaluated(lods.get(entity).ok());
+    let Some(rig) = target.rig_target else {
+      continue;
+    };
+    if layers.rig != Some(rig) {
+      layers.rig = Some(rig);
+      layers.bones.clear();
+      rig_bones(rig, &children, &names, &mut layers.bones);
+    }
+
+    if let Some(controller) = controllers.get(&animator.controller) {
+      let controller_layers = controller.additive_layers();
+      layers.layer_times.resize(controller_layers.len(), 0.0);
+      for (layer, layer_time) in controller_layers.iter().zip(layers.layer_times.iter_mut()) {
+        let Some(clip) = clips.get(&layer.clip) else {
+          continue;
+        };
+        *layer_time += delta * layer.speed;
+        if clip.duration() > 0.0 {
+          *layer_time = layer_time.rem_euclid(clip.duration());
+        }
+        if !evaluate {
+          continue;
+        }
+        let weight = layer.weight.resolve(input);
+        let (time, reference) = (*layer_time, layer.reference_time);
+        apply_additive(
+          clip,
+          time,
+          reference,
+          weight,
+          &layers.bones,
+          &mut transforms,
+        );
+      }
+    }
+
+    layers.one_shots.retain_mut(|one_shot| {
+      let Some(clip) = clips.get(&one_shot.clip) else {
+        // keep waiting for the clip to load
+        return true;
+      };
+      one_shot.time += delta;
+      if one_shot.time >= clip.duration() {
+        return false;
+      }
+      if evaluate {
+        let (time, weight) = (one_shot.time, one_shot.weight);
+        apply_additive(clip, time, 0.0, weight, &layers.bones, &mut transforms);
+      }
+      true
+    });
+  }
+}
+
+fn apply_additive(
+  clip: &AnimationClip,
+  time: f32,
+  reference_time: f32,
+  weight: f32,
+  bones: &[(Entity, EntityPath)],
+  transforms: &mut Query<&mut Transform>,
+) {
+  if weight == 0.0 {
+    return;
+  }
+  for (bone, path) in bones {
+    let Some(curves) = clip.get_curves_by_path(path) else {
+      continue;
+    };
+    let Ok(mut transform) = transforms.get_mut(*bone) else {
+      continue;
+    };
+    let mut sampled = Transform::IDENTITY;
+    let mut reference = Transform::IDENTITY;
+    for curve in curves {
+      sample_curve(curve, time, &mut sampled);
+      sample_curve(curve, reference_time, &mut reference);
+    }
+    let rotation = reference.rotation.inverse() * sampled.rotation;
+    transform.translation += (sampled.translation - reference.translation) * weight;
+    transform.rotation = (transform.rotation * Quat::IDENTITY.slerp(rotation, weight)).normalize();
+    transform.scale *= Vec3::ONE.lerp(sampled.scale / reference.scale, weight);
+  }
+}
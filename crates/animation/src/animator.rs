@@ -1,13 +1,16 @@
 use bevy::prelude::*;
 use std::collections::HashMap;
 
-use crate::AnimationController;
+use crate::{AdditiveLayers, AnimationBlend, AnimationController, PropertyPlayer};
 
 #[derive(Bundle)]
 pub struct AnimatedBundle<T: Asset + AnimationController> {
   pub animator: Animator<T>,
   pub params: AnimationControllerInput,
   pub data: AnimationControllerData<T>,
+  pub blend: AnimationBlend,
+  pub properties: PropertyPlayer,
+  pub additive: AdditiveLayers,
 }
 
 impl<T: Asset + AnimationController> Default for AnimatedBundle<T>
@@ -19,6 +22,9 @@ where
       animator: Animator::<T>::default(),
       params: AnimationControllerInput::default(),
       data: AnimationControllerData::default(),
+      blend: AnimationBlend::default(),
+      properties: PropertyPlayer::default(),
+      additive: AdditiveLayers::default(),
     }
   }
 }
@@ -37,6 +43,41 @@ impl<T: Asset> Default for Animator<T> {
   }
 }
 
+/// Makes this entity one of several named animators of `owner`, e.g. for an animated cape or
+/// weapon on a character. The rig path of the animator starts at the owner and rigs of different
+/// animators must not contain each other.
+#[derive(Component, Reflect)]
+pub struct AnimatorOf {
+  pub owner: Entity,
+  /// Receive every parameter set on the owner's [`AnimationControllerInput`]
+  pub shared_parameters: bool,
+}
+
+impl AnimatorOf {
+  pub fn new(owner: Entity) -> Self {
+    Self {
+      owner,
+      shared_parameters: true,
+    }
+  }
+}
+
+/// The named animators of an entity, kept up to date from their [`AnimatorOf`] and [`Name`].
+#[derive(Component, Default)]
+pub struct Animators {
+  animators: HashMap<String, Entity>,
+}
+
+impl Animators {
+  pub fn get(&self, name: &str) -> Option<Entity> {
+    self.animators.get(name).copied()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&str, Entity)> {
+    self.animators.iter().map(|(name, e)| (name.as_str(), *e))
+  }
+}
+
 #[derive(Component, Default, Reflect)]
 pub struct AnimatorTarget {
   pub rig_target: Option<Entity>,
@@ -52,6 +93,9 @@ pub struct AnimationControllerData<T: AnimationController> {
 }
 
 impl AnimationControllerInput {
+  pub fn get_parameter(&self, key: &str) -> f32 {
+    *self.parameters.get(key).unwrap_or(&0.0)
+  }
   pub fn set_parameter(&mut self, key: &'static str, value: f32) {
     if let Some(v) = self.parameters.get_mut(key) {
       if v != &value {
@@ -72,3 +116,64 @@ where
     }
   }
 }
+
+pub(crate) fn register_animators(
+  mut cmd: Commands,
+  animators: Query<(Entity, Ref<AnimatorOf>, Ref<Name>)>,
+  mut removed: RemovedComponents<AnimatorOf>,
+  mut owners: Query<&mut Animators>,
+) {
+  let changed: Vec<_> = animators
+    .iter()
+    .filter(|(_, animator_of, name)| animator_of.is_changed() || name.is_changed())
+    .collect();
+  let stale: Vec<_> = removed
+    .read()
+    .chain(changed.iter().map(|(e, ..)| *e))
+    .collect();
+  if !stale.is_empty() {
+    for mut animators in owners.iter_mut() {
+      animators.animators.retain(|_, e| !stale.contains(e));
+    }
+  }
+  let mut new_owners = HashMap::<Entity, Animators>::new();
+  for (entity, animator_of, name) in changed {
+    let name = name.as_str().to_owned();
+    match owners.get_mut(animator_of.owner) {
+      Ok(mut animators) => animators.animators.insert(name, entity),
+      Err(_) => new_owners
+        .entry(animator_of.owner)
+        .or_default()
+        .animators
+        .insert(name, entity),
+    };
+  }
+  for (owner, animators) in new_owners {
+    if let Some(mut owner) = cmd.get_entity(owner) {
+      owner.insert(animators);
+    }
+  }
+}
+
+/// Copies parameters set on an owner to its animators that share them.
+pub(crate) fn share_parameters(
+  animators: Query<(Entity, Ref<AnimatorOf>)>,
+  mut inputs: Query<&mut AnimationControllerInput>,
+) {
+  for (entity, animator_of) in animators.iter() {
+    if !animator_of.shared_parameters {
+      continue;
+    }
+    let Ok([owner, mut input]) = inputs.get_many_mut([animator_of.owner, entity]) else {
+      continue;
+    };
+    if !owner.is_changed() && !animator_of.is_changed() {
+      continue;
+    }
+    for (key, value) in owner.parameters.iter() {
+      if input.parameters.get(key) != Some(value) {
+        input.parameters.insert(key.clone(), *value);
+      }
+    }
+  }
+}
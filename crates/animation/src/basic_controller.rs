@@ -1,86 +1,128 @@
-use assets::RonAsset;
-use bevy::{asset::LoadContext, prelude::*};
-use serde::Deserialize;
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use assets::{NestedPath, RonAsset, RonAssetLoaderError};
+use bevy::{
+  asset::{AssetPath, LoadContext},
+  gltf::Gltf,
+  prelude::*,
+  utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+  borrow::Cow,
+  collections::{BTreeMap, HashMap},
+  sync::Arc,
+};
 
-use crate::{AnimationController, AnimationControllerInput};
+use crate::{
+  blend::{Blend, BlendCurve, BlendMode},
+  clips::ClipLoader,
+  expression::ConditionExpr,
+  AdditiveLayer, AdditiveWeight, AnimationController, AnimationControllerInput, AnimationOutput,
+  Mirror, PropertyAnimation,
+};
 
-#[derive(Deserialize, Asset, TypePath)]
+#[derive(Deserialize, Serialize, Clone, Asset, TypePath)]
 pub struct BasicAnimationController {
-  nodes: HashMap<BasicNodeId, BasicAnimationNode>,
-  edges: Vec<BasicAnimationTransition>,
-  default_node: BasicNodeId,
-  #[serde(skip_deserializing)]
-  assets: Option<BasicAnimationControllerAssets>,
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  extends: Option<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  include: Vec<String>,
+  /// glTF file that node clips are looked up in by name
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  model: Option<String>,
+  /// Retarget map applied to every clip, for clips authored for a different skeleton
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  retarget: Option<String>,
+  /// How nodes with `mirror` are mirrored, flipping X and pairing `.L`/`.R` bones by default
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  mirror: Option<Mirror>,
+  #[serde(default, serialize_with = "sorted")]
+  pub(crate) nodes: HashMap<BasicNodeId, BasicAnimationNode>,
+  #[serde(default)]
+  pub(crate) edges: Vec<BasicAnimationTransition>,
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  pub(crate) default_node: Option<BasicNodeId>,
+  /// Clips layered on top of every node
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  additive: Vec<BasicAdditiveLayer>,
+  /// Positions of the nodes in the graph editor
+  #[serde(
+    default,
+    skip_serializing_if = "HashMap::is_empty",
+    serialize_with = "sorted"
+  )]
+  pub(crate) layout: HashMap<BasicNodeId, (f32, f32)>,
+  #[serde(skip)]
+  pub(crate) assets: Option<BasicAnimationControllerAssets>,
+  /// The document as read, before its paths were resolved and its bases merged in
+  #[serde(skip)]
+  written: Option<Box<BasicAnimationController>>,
 }
 
-#[derive(Default, Reflect)]
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Reflect)]
 pub struct BasicAnimationControllerData {
-  active_node: Option<BasicNodeId>,
+  pub(crate) active_node: Option<BasicNodeId>,
+}
+
+impl BasicAnimationControllerData {
+  pub fn active_node(&self) -> Option<&BasicNodeId> {
+    self.active_node.as_ref()
+  }
 }
 impl BasicAnimationController {
-  fn get_transition(
+  /// Fields holding paths of nested assets, except for bases.
+  fn nested_path_fields(&mut self) -> impl Iterator<Item = &mut Option<String>> {
+    let nodes = self
+      .nodes
+      .values_mut()
+      .flat_map(|node| [&mut node.animation, &mut node.properties]);
+    let additive = self.additive.iter_mut().map(|layer| &mut layer.animation);
+    [&mut self.model, &mut self.retarget]
+      .into_iter()
+      .chain(nodes)
+      .chain(additive)
+  }
+
+  pub(crate) fn get_transition(
     &self,
     parameters: &HashMap<String, f32>,
     trigger: Option<&str>,
     data: &BasicAnimationControllerData,
-  ) -> Option<BasicAnimationTransition> {
+  ) -> Option<Cow<'_, BasicAnimationTransition>> {
     let Some(active_node_id) = &data.active_node else {
-      return Some(BasicAnimationTransition {
-        to: self.default_node.clone(),
+      let Some(default_node) = &self.default_node else {
+        warn!("Animation controller has no default node");
+        return None;
+      };
+      return Some(Cow::Owned(BasicAnimationTransition {
+        to: default_node.clone(),
         ..default()
-      });
+      }));
     };
 
-    let Some(transition) = self
+    self
       .edges
       .iter()
-      .filter(|e| {
+      .find(|e| {
         if let Some(from) = &e.from {
           if active_node_id != from {
             return false;
           }
         }
-        e.conditions.iter().all(|c| match c {
-          BasicAnimationTransitionCondition::GreaterThan(name, v) => {
-            parameters.get(name.as_str()).unwrap_or(&0.0) > v
-          }
-          BasicAnimationTransitionCondition::LessThan(name, v) => {
-            parameters.get(name.as_str()).unwrap_or(&0.0) < v
-          }
-          BasicAnimationTransitionCondition::Trigger(t) => {
-            if let Some(trigger) = trigger {
-              t == trigger
-            } else {
-              false
-            }
-          }
-        })
+        e.conditions.iter().all(|c| c.evaluate(parameters, trigger))
       })
-      .nth(0)
-    else {
-      return None;
-    };
-
-    Some(transition.clone())
+      .map(Cow::Borrowed)
   }
-}
-impl AnimationController for BasicAnimationController {
-  type ControllerData = BasicAnimationControllerData;
-  fn update_animation(
+
+  fn enter(
     &self,
-    parameters: &AnimationControllerInput,
-    trigger: Option<&str>,
-    data: &mut Self::ControllerData,
-    player: &mut AnimationPlayer,
+    transition: &BasicAnimationTransition,
+    data: &mut BasicAnimationControllerData,
+    output: &mut AnimationOutput,
   ) {
     let Some(assets) = &self.assets else {
       warn!("Cannot compute transition, assets not found");
       return;
     };
-    let Some(transition) = self.get_transition(&parameters.parameters, trigger, data) else {
-      return;
-    };
     let Some(node) = self.nodes.get(&transition.to) else {
       warn!(
         "Animation node {:?} not found, cannot execute transition",
@@ -88,75 +130,386 @@ impl AnimationController for BasicAnimationController {
       );
       return;
     };
-    let Some(anim) = assets.animations.get(&transition.to) else {
+    let animation = assets.animations.get(&transition.to);
+    let properties = assets.properties.get(&transition.to);
+    if animation.is_none() && properties.is_none() {
       warn!(
         "Animation {:?} not found, cannot execute transition",
         transition.to
       );
       return;
-    };
+    }
 
-    data.active_node = Some(transition.to);
-    player.play_with_transition(
-      anim.clone(),
-      Duration::from_secs_f32(transition.transition_duration_seconds),
-    );
-    player.set_speed(node.speed);
-    if node.repeat {
-      player.set_repeat(bevy::animation::RepeatAnimation::Forever);
-    } else {
-      player.set_repeat(bevy::animation::RepeatAnimation::Count(1));
+    data.active_node = Some(transition.to.clone());
+    if let Some(animation) = animation {
+      let player = &mut *output.player;
+      output.blend.play(
+        player,
+        animation.clone(),
+        &Blend {
+          duration_seconds: transition.transition_duration_seconds,
+          curve: transition.curve.clone(),
+          mode: transition.mode,
+        },
+      );
+      player.set_speed(node.speed);
+      if node.repeat {
+        player.set_repeat(bevy::animation::RepeatAnimation::Forever);
+      } else {
+        player.set_repeat(bevy::animation::RepeatAnimation::Count(1));
+      }
+    }
+    match properties {
+      Some(properties) => output
+        .properties
+        .play(properties.clone(), node.speed, node.repeat),
+      None => output.properties.stop(),
+    }
+  }
+}
+impl AnimationController for BasicAnimationController {
+  type ControllerData = BasicAnimationControllerData;
+  fn additive_layers(&self) -> &[AdditiveLayer] {
+    self.assets.as_ref().map_or(&[], |a| a.additive.as_slice())
+  }
+  fn update_animation(
+    &self,
+    parameters: &AnimationControllerInput,
+    trigger: Option<&str>,
+    data: &mut Self::ControllerData,
+    output: &mut AnimationOutput,
+  ) {
+    if let Some(transition) = self.get_transition(&parameters.parameters, trigger, data) {
+      self.enter(&transition, data, output);
+    }
+  }
+  fn reloaded(
+    &self,
+    parameters: &AnimationControllerInput,
+    data: &mut Self::ControllerData,
+    output: &mut AnimationOutput,
+  ) {
+    if let Some(active) = &data.active_node {
+      if !self.nodes.contains_key(active) {
+        warn!("Animation node {active:?} was removed, falling back to the default node");
+        data.active_node = None;
+      }
+    }
+    match self.get_transition(&parameters.parameters, None, data) {
+      Some(transition) => self.enter(&transition, data, output),
+      // stay in the active node, its clip or settings may have changed
+      None => {
+        if let Some(active) = data.active_node.clone() {
+          let transition = BasicAnimationTransition {
+            to: active,
+            ..default()
+          };
+          self.enter(&transition, data, output);
+        }
+      }
     }
   }
 }
 
+#[derive(Clone)]
 pub struct BasicAnimationControllerAssets {
+  pub model: Option<Handle<Gltf>>,
   pub animations: HashMap<BasicNodeId, Handle<AnimationClip>>,
+  pub properties: HashMap<BasicNodeId, Handle<PropertyAnimation>>,
+  pub additive: Vec<AdditiveLayer>,
+  /// The clips that retargeted and mirrored clips, labeled assets of the controller, were made
+  /// from.
+  pub sources: HashMap<AssetId<AnimationClip>, AssetPath<'static>>,
 }
 
-#[derive(PartialEq, Hash, Eq, Debug, Deserialize, Clone, Default, Reflect)]
-pub struct BasicNodeId(Arc<String>);
+#[derive(
+  PartialEq, Hash, Eq, PartialOrd, Ord, Debug, Deserialize, Serialize, Clone, Default, Reflect,
+)]
+pub struct BasicNodeId(pub(crate) Arc<String>);
 
-#[derive(Deserialize, Default, Clone)]
+impl BasicNodeId {
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+}
+
+#[derive(Deserialize, Serialize, Default, Clone)]
 pub struct BasicAnimationTransition {
+  /// Identifies the edge so a controller extending this one can override it
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  pub id: Option<String>,
   pub from: Option<BasicNodeId>, // any node if node
   pub to: BasicNodeId,
   pub transition_duration_seconds: f32,
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub curve: BlendCurve,
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub mode: BlendMode,
   pub enabled: bool,
   pub conditions: Vec<BasicAnimationTransitionCondition>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct BasicAnimationNode {
-  pub animation: String,
+  /// Asset path of the clip, e.g. `"char.glb#Animation2"`, relative to the controller's directory
+  /// unless it starts with `/`
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  pub animation: Option<String>,
+  /// Name of the clip in the controller's `model`
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  pub clip: Option<String>,
+  /// Asset path of a property animation played while the node is active
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  pub properties: Option<String>,
+  /// Play the clip mirrored left to right
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub mirror: bool,
   pub repeat: bool,
   pub speed: f32,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Serialize, Clone)]
+pub struct BasicAdditiveLayer {
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  pub animation: Option<String>,
+  #[serde(default, with = "some", skip_serializing_if = "Option::is_none")]
+  pub clip: Option<String>,
+  pub weight: AdditiveWeight,
+  /// Time of the pose in the clip that the layer is relative to
+  #[serde(default, skip_serializing_if = "is_default")]
+  pub reference_time: f32,
+  #[serde(default = "default_speed")]
+  pub speed: f32,
+}
+
+fn default_speed() -> f32 {
+  1.0
+}
+
+#[derive(Deserialize, Serialize, Clone)]
 pub enum BasicAnimationTransitionCondition {
   GreaterThan(String, f32),
   LessThan(String, f32),
   Trigger(String),
+  Expression(ConditionExpr),
+}
+
+impl BasicAnimationTransitionCondition {
+  fn evaluate(&self, parameters: &HashMap<String, f32>, trigger: Option<&str>) -> bool {
+    match self {
+      BasicAnimationTransitionCondition::GreaterThan(name, v) => {
+        parameters.get(name.as_str()).unwrap_or(&0.0) > v
+      }
+      BasicAnimationTransitionCondition::LessThan(name, v) => {
+        parameters.get(name.as_str()).unwrap_or(&0.0) < v
+      }
+      BasicAnimationTransitionCondition::Trigger(t) => trigger == Some(t.as_str()),
+      BasicAnimationTransitionCondition::Expression(expr) => expr.evaluate(parameters, trigger),
+    }
+  }
 }
 
 impl RonAsset for BasicAnimationController {
   type NestedAssets = BasicAnimationControllerAssets;
-  fn construct_nested_assets<'a>(&mut self, load_context: &'a mut LoadContext) {
-    self.assets = Some(BasicAnimationControllerAssets {
-      animations: self
-        .nodes
-        .iter()
-        .map(|(node_id, node)| {
-          (
-            node_id.clone(),
-            load_context.load::<AnimationClip>(&node.animation),
-          )
-        })
-        .collect::<HashMap<_, _>>(),
-    });
+  type Settings = ();
+  fn construct_nested_assets<'a>(
+    &'a mut self,
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), RonAssetLoaderError>> {
+    Box::pin(async move {
+      let mirror = self.mirror.clone().unwrap_or_default();
+      let mut clips = ClipLoader::new(load_context, self.retarget.as_deref(), mirror).await?;
+      let (model, named) = match &self.model {
+        Some(path) => {
+          let loaded = load_context.load_direct(path).await?;
+          let Some(gltf) = loaded.get::<Gltf>() else {
+            return Err(RonAssetLoaderError::Invalid(format!(
+              "model {path:?} is not a glTF file"
+            )));
+          };
+          let named = gltf
+            .named_animations
+            .iter()
+            .filter_map(|(name, clip)| Some((name.clone(), clip.path()?.clone_owned())))
+            .collect();
+          clips.add_file(AssetPath::parse(path).into_owned(), loaded);
+          (Some(load_context.load(path)), named)
+        }
+        None => (None, HashMap::new()),
+      };
+
+      let mut animations = HashMap::new();
+      let mut properties = HashMap::new();
+      let mut missing = Vec::new();
+      // in a fixed order, generated clips are labeled by their index
+      let mut nodes: Vec<_> = self.nodes.iter().collect();
+      nodes.sort_by_key(|(node_id, _)| *node_id);
+      for (node_id, node) in nodes {
+        if let Some(path) = &node.properties {
+          properties.insert(node_id.clone(), load_context.load(path));
+        }
+        if node.clip.is_none() && node.animation.is_none() {
+          if node.properties.is_some() {
+            continue;
+          }
+          return Err(RonAssetLoaderError::Invalid(format!(
+            "node {:?} has no clip, animation or properties",
+            node_id.0
+          )));
+        }
+        if let Some(path) = clip_path(&named, &node.clip, &node.animation, &mut missing) {
+          let clip = clips.load(load_context, path, node.mirror).await?;
+          animations.insert(node_id.clone(), clip);
+        }
+      }
+      let mut additive = Vec::new();
+      for layer in self.additive.iter() {
+        let Some(path) = clip_path(&named, &layer.clip, &layer.animation, &mut missing) else {
+          continue;
+        };
+        additive.push(AdditiveLayer {
+          clip: clips.load(load_context, path, false).await?,
+          weight: layer.weight.clone(),
+          reference_time: layer.reference_time,
+          speed: layer.speed,
+        });
+      }
+      if !missing.is_empty() {
+        missing.sort();
+        return Err(RonAssetLoaderError::Invalid(format!(
+          "animations not found in {:?}: {}",
+          self.model.as_deref().unwrap_or("<no model>"),
+          missing.join(", ")
+        )));
+      }
+
+      self.assets = Some(BasicAnimationControllerAssets {
+        model,
+        animations,
+        properties,
+        additive,
+        sources: clips.sources(),
+      });
+      Ok(())
+    })
   }
   fn extensions() -> &'static [&'static str] {
     &["basic.anim.ron"]
   }
+  fn bases(&self) -> Vec<String> {
+    self.extends.iter().chain(&self.include).cloned().collect()
+  }
+  fn resolve_paths(&mut self, document: &AssetPath) -> Result<(), RonAssetLoaderError> {
+    self.written = Some(Box::new(self.clone()));
+    // bases are resolved by the loader, before they are merged in
+    self
+      .nested_path_fields()
+      .try_for_each(|path| path.resolve(document))
+  }
+  fn prepare_save(&mut self) {
+    // saved with its bases and paths as written, so it still reads the same documents
+    if let Some(written) = self.written.take() {
+      *self = *written;
+    }
+  }
+  fn validate(&self) -> Vec<String> {
+    self.lint()
+  }
+  fn nested_paths(&self) -> Vec<AssetPath<'static>> {
+    let Some(assets) = &self.assets else {
+      return Vec::new();
+    };
+    // generated clips are reported as the clip they were made from, not as part of the controller
+    let clip = |h: &Handle<AnimationClip>| assets.sources.get(&h.id()).or(h.path()).cloned();
+    let model = assets.model.as_ref().and_then(|h| h.path()).cloned();
+    let clips = assets.animations.values().filter_map(clip);
+    let additive = assets.additive.iter().filter_map(|layer| clip(&layer.clip));
+    let properties = assets.properties.values().filter_map(|h| h.path()).cloned();
+    model
+      .into_iter()
+      .chain(clips)
+      .chain(additive)
+      .chain(properties)
+      .collect()
+  }
+  fn merge_onto(&mut self, mut base: Self) {
+    for edge in self.edges.drain(..) {
+      let existing = edge
+        .id
+        .as_ref()
+        .and_then(|id| base.edges.iter_mut().find(|e| e.id.as_ref() == Some(id)));
+      if let Some(existing) = existing {
+        *existing = edge;
+      } else {
+        base.edges.push(edge);
+      }
+    }
+    base.nodes.extend(self.nodes.drain());
+    self.nodes = base.nodes;
+    self.edges = base.edges;
+    self.default_node = self.default_node.take().or(base.default_node);
+    self.model = self.model.take().or(base.model);
+    self.retarget = self.retarget.take().or(base.retarget);
+    self.mirror = self.mirror.take().or(base.mirror);
+    base.additive.append(&mut self.additive);
+    self.additive = base.additive;
+    base.layout.extend(self.layout.drain());
+    self.layout = base.layout;
+  }
+}
+
+/// Path of a clip referenced either by name in the controller's model or by asset path, recording
+/// names the model does not have in `missing`.
+fn clip_path(
+  named: &HashMap<String, AssetPath<'static>>,
+  clip: &Option<String>,
+  animation: &Option<String>,
+  missing: &mut Vec<String>,
+) -> Option<AssetPath<'static>> {
+  match (clip, animation) {
+    (Some(name), _) => {
+      let path = named.get(name).cloned();
+      if path.is_none() {
+        missing.push(name.clone());
+      }
+      path
+    }
+    (None, Some(path)) => Some(AssetPath::parse(path).into_owned()),
+    (None, None) => None,
+  }
+}
+
+/// Writes an optional field as just its value, for fields that are left out when `None`.
+pub(crate) mod some {
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  pub fn serialize<S: Serializer, T: Serialize>(
+    value: &Option<T>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    match value {
+      Some(value) => value.serialize(serializer),
+      None => serializer.serialize_none(),
+    }
+  }
+
+  pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+  where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+  {
+    T::deserialize(deserializer).map(Some)
+  }
+}
+
+/// Writes maps in key order so saved documents diff well.
+fn sorted<S: Serializer, K: Ord + Serialize, V: Serialize>(
+  map: &HashMap<K, V>,
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+  *value == T::default()
 }
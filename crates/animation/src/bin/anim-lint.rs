@@ -0,0 +1,353 @@
+//! Checks a `.basic.anim.ron` controller and the model it uses without opening a window, then
+//! optionally plays a parameter timeline through it and prints the nodes it goes through.
+//!
+//! ```text
+//! anim-lint <controller> [--timeline <file.ron>] [--assets <dir>]
+//! anim-lint --migrate <file>... [--assets <dir>]
+//! ```
+//!
+//! The controller path is relative to the assets folder. A timeline is a list of events such as
+//! `[(time: 0.0, set: {"velocity": 1.0}), (time: 2.0, trigger: Some("jump"))]`. The controller is
+//! loaded in strict mode, so misspelled fields are errors. Exits with an error status when anything
+//! is wrong, for use in pre-commit checks.
+//!
+//! With `--migrate` it instead rewrites controllers, property animations and retarget maps that
+//! were written for older versions of their format to the latest one. Comments are not kept.
+
+use animation::{
+  AnimationControllerPlugin, BasicAnimationController, BasicAnimationControllerData,
+  PropertyAnimation, RetargetMap,
+};
+use assets::{migrate_file, RonAsset, RonAssetLoaderError, RonAssetSettings};
+use bevy::{
+  asset::{LoadState, RecursiveDependencyLoadState},
+  gltf::Gltf,
+  log::{Level, LogPlugin},
+  prelude::*,
+  render::{settings::WgpuSettings, RenderPlugin},
+  window::ExitCondition,
+  winit::WinitPlugin,
+};
+use serde::Deserialize;
+use std::{
+  collections::{BTreeSet, HashMap},
+  path::{Path, PathBuf},
+  process::ExitCode,
+  time::{Duration, Instant},
+};
+
+const LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+struct TimelineEvent {
+  time: f32,
+  #[serde(default)]
+  set: HashMap<String, f32>,
+  #[serde(default)]
+  trigger: Option<String>,
+}
+
+struct Args {
+  controller: String,
+  timeline: Option<PathBuf>,
+  assets: PathBuf,
+  /// Files to rewrite to the latest version instead of checking the controller.
+  migrate: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+  let mut files = Vec::new();
+  let mut timeline = None;
+  let mut assets = PathBuf::from("assets");
+  let mut migrate = false;
+  let mut args = std::env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--timeline" => timeline = Some(args.next().ok_or("--timeline needs a file")?.into()),
+      "--assets" => assets = args.next().ok_or("--assets needs a directory")?.into(),
+      "--migrate" => migrate = true,
+      _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+      _ if migrate || files.is_empty() => files.push(arg),
+      _ => return Err(format!("unexpected argument {arg}")),
+    }
+  }
+  if migrate && timeline.is_some() {
+    return Err("--timeline does not apply to --migrate".to_owned());
+  }
+  let controller = match migrate {
+    true => String::new(),
+    false => files.pop().ok_or("no controller given")?,
+  };
+  if migrate && files.is_empty() {
+    return Err("no files to migrate".to_owned());
+  }
+  Ok(Args {
+    controller,
+    migrate: files,
+    timeline,
+    // the asset reader resolves relative paths against the crate it was built in
+    assets: assets
+      .canonicalize()
+      .map_err(|e| format!("assets folder {}: {e}", assets.display()))?,
+  })
+}
+
+fn main() -> ExitCode {
+  let args = match parse_args() {
+    Ok(args) => args,
+    Err(error) => {
+      eprintln!("{error}");
+      eprintln!("usage: anim-lint <controller> [--timeline <file.ron>] [--assets <dir>]");
+      eprintln!("       anim-lint --migrate <file>... [--assets <dir>]");
+      return ExitCode::from(2);
+    }
+  };
+  if !args.migrate.is_empty() {
+    return migrate(&args.assets, &args.migrate);
+  }
+  let timeline = match args.timeline.as_ref().map(read_timeline).transpose() {
+    Ok(timeline) => timeline,
+    Err(error) => {
+      eprintln!("{error}");
+      return ExitCode::from(2);
+    }
+  };
+
+  let mut app = App::new();
+  app.add_plugins((
+    DefaultPlugins
+      .set(AssetPlugin {
+        file_path: args.assets.to_string_lossy().into_owned(),
+        ..default()
+      })
+      .set(WindowPlugin {
+        primary_window: None,
+        exit_condition: ExitCondition::DontExit,
+        close_when_requested: false,
+      })
+      .set(RenderPlugin {
+        render_creation: WgpuSettings {
+          backends: None,
+          ..default()
+        }
+        .into(),
+      })
+      .set(LogPlugin {
+        level: Level::WARN,
+        filter: "wgpu=error,bevy_gltf=error".to_owned(),
+      })
+      .disable::<WinitPlugin>(),
+    AnimationControllerPlugin,
+  ));
+  app.finish();
+  app.cleanup();
+
+  let handle: Handle<BasicAnimationController> = app
+    .world
+    .resource::<AssetServer>()
+    .load_with_settings(&args.controller, |settings: &mut RonAssetSettings<()>| {
+      settings.strict = true
+    });
+  let started = Instant::now();
+  loop {
+    app.update();
+    let server = app.world.resource::<AssetServer>();
+    if server.get_load_state(&handle) == Some(LoadState::Failed) {
+      // the loader error is logged by the asset server
+      eprintln!("error: {} failed to load", args.controller);
+      return ExitCode::FAILURE;
+    }
+    let done = match server.get_recursive_dependency_load_state(&handle) {
+      Some(RecursiveDependencyLoadState::Loaded) => true,
+      // one failed dependency fails the whole tree while others may still be loading
+      Some(RecursiveDependencyLoadState::Failed) => app
+        .world
+        .resource::<Assets<BasicAnimationController>>()
+        .get(&handle)
+        .is_some_and(|controller| {
+          let model = controller.model().map(|h| h.id().untyped());
+          let clips = controller.node_clips().map(|(_, h)| h.id().untyped());
+          !model
+            .into_iter()
+            .chain(clips)
+            .any(|id| server.get_load_state(id) == Some(LoadState::Loading))
+        }),
+      _ => false,
+    };
+    if done {
+      break;
+    }
+    if started.elapsed() > LOAD_TIMEOUT {
+      eprintln!("error: {} did not load in time", args.controller);
+      return ExitCode::FAILURE;
+    }
+    std::thread::sleep(Duration::from_millis(1));
+  }
+
+  let world = &app.world;
+  let controller = world
+    .resource::<Assets<BasicAnimationController>>()
+    .get(&handle)
+    .unwrap();
+  let mut problems = controller.lint();
+
+  if let Some(model) = controller.model() {
+    let server = world.resource::<AssetServer>();
+    let path = server
+      .get_path(model)
+      .map_or_else(String::new, |p| p.to_string());
+    match world.resource::<Assets<Gltf>>().get(model) {
+      Some(gltf) => {
+        let names: BTreeSet<_> = gltf.named_animations.keys().collect();
+        println!("clips in {path}:");
+        for name in names {
+          println!("  {name}");
+        }
+        let unnamed = gltf.animations.len() - gltf.named_animations.len();
+        if unnamed > 0 {
+          println!("  and {unnamed} unnamed");
+        }
+      }
+      None => problems.push(format!("model {path} did not load")),
+    }
+  }
+  let clips = world.resource::<Assets<AnimationClip>>();
+  let server = world.resource::<AssetServer>();
+  let mut nodes: Vec<_> = controller.node_clips().collect();
+  nodes.sort_by_key(|(id, _)| *id);
+  for (id, clip) in nodes {
+    if !clips.contains(clip) {
+      let path = server
+        .get_path(clip)
+        .map_or_else(String::new, |p| p.to_string());
+      problems.push(format!(
+        "node {:?} plays {path} which did not load",
+        id.as_str()
+      ));
+    }
+  }
+
+  let parameters = controller.parameters();
+  let triggers = controller.triggers();
+  println!("parameters: {}", join(parameters.iter().copied()));
+  println!("triggers: {}", join(triggers.iter().copied()));
+
+  if let Some(mut timeline) = timeline {
+    timeline.sort_by(|a, b| a.time.total_cmp(&b.time));
+    for event in timeline.iter() {
+      for name in event.set.keys() {
+        if !parameters.contains(name.as_str()) {
+          problems.push(format!(
+            "timeline sets {name:?} at {:.2}s, which no condition reads",
+            event.time
+          ));
+        }
+      }
+      if let Some(trigger) = &event.trigger {
+        if !triggers.contains(trigger.as_str()) {
+          problems.push(format!(
+            "timeline fires {trigger:?} at {:.2}s, which no condition checks",
+            event.time
+          ));
+        }
+      }
+    }
+    let set: BTreeSet<_> = timeline
+      .iter()
+      .flat_map(|e| e.set.keys().map(String::as_str))
+      .collect();
+    for name in parameters.difference(&set) {
+      println!("note: parameter {name:?} is never set by the timeline");
+    }
+    simulate(controller, &timeline);
+  }
+
+  for problem in problems.iter() {
+    eprintln!("error: {problem}");
+  }
+  if problems.is_empty() {
+    ExitCode::SUCCESS
+  } else {
+    ExitCode::FAILURE
+  }
+}
+
+/// Rewrites each file with the asset type its extension belongs to.
+fn migrate(assets: &Path, files: &[String]) -> ExitCode {
+  fn by_extension<T: RonAsset + serde::de::DeserializeOwned>(
+    file: &str,
+    path: &Path,
+  ) -> Option<Result<bool, RonAssetLoaderError>> {
+    let known = T::extensions()
+      .iter()
+      .any(|e| file.ends_with(&format!(".{e}")));
+    known.then(|| migrate_file::<T>(path))
+  }
+
+  let mut failed = false;
+  for file in files {
+    let path = assets.join(file);
+    let result = by_extension::<BasicAnimationController>(file, &path)
+      .or_else(|| by_extension::<PropertyAnimation>(file, &path))
+      .or_else(|| by_extension::<RetargetMap>(file, &path));
+    match result {
+      Some(Ok(true)) => println!("migrated {file}"),
+      Some(Ok(false)) => println!("{file} is up to date"),
+      Some(Err(error)) => {
+        eprintln!("error: {file}: {error}");
+        failed = true;
+      }
+      None => {
+        eprintln!("error: {file} is not a RON animation asset");
+        failed = true;
+      }
+    }
+  }
+  match failed {
+    true => ExitCode::FAILURE,
+    false => ExitCode::SUCCESS,
+  }
+}
+
+fn read_timeline(path: &PathBuf) -> Result<Vec<TimelineEvent>, String> {
+  let source =
+    std::fs::read_to_string(path).map_err(|e| format!("timeline {}: {e}", path.display()))?;
+  serde_ron::from_str(&source).map_err(|e| format!("timeline {}: {e}", path.display()))
+}
+
+/// Prints the node the controller is in after every event of the timeline.
+fn simulate(controller: &BasicAnimationController, timeline: &[TimelineEvent]) {
+  let mut parameters = HashMap::new();
+  let mut data = BasicAnimationControllerData::default();
+  let node = |data: &BasicAnimationControllerData| {
+    data
+      .active_node()
+      .map_or("<none>".to_owned(), |id| id.as_str().to_owned())
+  };
+
+  // a new animator is evaluated once before any parameter is set
+  controller.simulate(&parameters, None, &mut data);
+  println!("{:>8.2}s  {}", 0.0, node(&data));
+  for event in timeline {
+    parameters.extend(event.set.iter().map(|(k, v)| (k.clone(), *v)));
+    let mut changes: Vec<_> = event.set.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    changes.sort();
+    changes.extend(event.trigger.iter().map(|t| format!("trigger {t}")));
+    let before = node(&data);
+    let entered = controller.simulate(&parameters, event.trigger.as_deref(), &mut data);
+    let state = match entered {
+      Some(id) if id.as_str() != before => format!("{before} -> {}", id.as_str()),
+      _ => before,
+    };
+    println!("{:>8.2}s  {state:<24} {}", event.time, changes.join(", "));
+  }
+}
+
+fn join<'a>(names: impl Iterator<Item = &'a str>) -> String {
+  let names: Vec<_> = names.collect();
+  if names.is_empty() {
+    "none".to_owned()
+  } else {
+    names.join(", ")
+  }
+}
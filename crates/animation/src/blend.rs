@@ -0,0 +1,353 @@
+use bevy::{animation::RepeatAnimation, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{lod::is_evaluated, AnimationLod, AnimatorTarget};
+
+/// Easing applied to the blend weight over the course of a transition.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub enum BlendCurve {
+  #[default]
+  Linear,
+  EaseIn,
+  EaseOut,
+  EaseInOut,
+  /// CSS style `cubic-bezier(x1, y1, x2, y2)`
+  CubicBezier(f32, f32, f32, f32),
+  /// Piecewise linear `(time, weight)` pairs, with time normalized to `0..=1`
+  Keyframes(Arc<[(f32, f32)]>),
+}
+
+impl BlendCurve {
+  pub fn sample(&self, t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    match self {
+      BlendCurve::Linear => t,
+      BlendCurve::EaseIn => t * t * t,
+      BlendCurve::EaseOut => 1.0 - (1.0 - t).powi(3),
+      BlendCurve::EaseInOut => t * t * (3.0 - 2.0 * t),
+      BlendCurve::CubicBezier(x1, y1, x2, y2) => cubic_bezier(*x1, *y1, *x2, *y2, t),
+      BlendCurve::Keyframes(keys) => {
+        let Some(next) = keys.iter().position(|(time, _)| *time >= t) else {
+          return keys.last().map_or(t, |(_, w)| *w);
+        };
+        if next == 0 {
+          return keys[0].1;
+        }
+        let (t0, w0) = keys[next - 1];
+        let (t1, w1) = keys[next];
+        if t1 <= t0 {
+          return w1;
+        }
+        w0 + (w1 - w0) * (t - t0) / (t1 - t0)
+      }
+    }
+  }
+}
+
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+  let bezier = |a: f32, b: f32, s: f32| {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * s * a + 3.0 * inv * s * s * b + s * s * s
+  };
+  // x(s) is monotonic for x1, x2 in 0..=1 so bisection always converges
+  let (mut lo, mut hi) = (0.0, 1.0);
+  for _ in 0..24 {
+    let mid = (lo + hi) * 0.5;
+    if bezier(x1.clamp(0.0, 1.0), x2.clamp(0.0, 1.0), mid) < x {
+      lo = mid;
+    } else {
+      hi = mid;
+    }
+  }
+  bezier(y1, y2, (lo + hi) * 0.5)
+}
+
+/// How the outgoing pose is combined with the incoming animation during a transition.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum BlendMode {
+  /// Keep playing the previous clip and cross-fade it into the new one
+  #[default]
+  Crossfade,
+  /// Blend from the pose the rig was in when the transition started
+  Frozen,
+  /// Apply the difference between the old pose and the new animation and decay it to zero
+  Inertialized,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Blend {
+  pub duration_seconds: f32,
+  pub curve: BlendCurve,
+  pub mode: BlendMode,
+}
+
+/// Blending state for the rig driven by an [`crate::Animator`].
+#[derive(Component, Default)]
+pub struct AnimationBlend {
+  pub(crate) active: Option<ActiveBlend>,
+}
+
+pub(crate) struct ActiveBlend {
+  pub(crate) blend: Blend,
+  pub(crate) elapsed: f32,
+  pub(crate) source: BlendSource,
+}
+
+pub(crate) enum BlendSource {
+  Capture,
+  Clip {
+    clip: Handle<AnimationClip>,
+    seek_time: f32,
+    speed: f32,
+    repeat: bool,
+    bones: Vec<(Entity, EntityPath)>,
+  },
+  Pose(Vec<(Entity, Transform)>),
+  Offset(Vec<(Entity, Transform)>),
+}
+
+impl AnimationBlend {
+  /// Switch `player` to `clip`, blending from the current pose as configured by `blend`.
+  pub fn play(&mut self, player: &mut AnimationPlayer, clip: Handle<AnimationClip>, blend: &Blend) {
+    if player.is_playing_clip(&clip) && !player.is_paused() {
+      return;
+    }
+    if blend.duration_seconds <= 0.0 {
+      self.active = None;
+    } else {
+      // a crossfade interrupting another transition starts from the blended pose instead
+      let source = if blend.mode == BlendMode::Crossfade && self.active.is_none() {
+        BlendSource::Clip {
+          clip: player.animation_clip().clone(),
+          seek_time: player.seek_time(),
+          speed: player.speed(),
+          repeat: matches!(player.repeat_mode(), RepeatAnimation::Forever),
+          bones: Vec::new(),
+        }
+      } else {
+        BlendSource::Capture
+      };
+      self.active = Some(ActiveBlend {
+        blend: blend.clone(),
+        elapsed: 0.0,
+        source,
+      });
+    }
+    player.start(clip);
+  }
+
+  pub fn is_blending(&self) -> bool {
+    self.active.is_some()
+  }
+}
+
+pub(crate) fn capture_blend_sources(
+  mut qry: Query<(&AnimatorTarget, &mut AnimationBlend)>,
+  children: Query<&Children>,
+  names: Query<&Name>,
+  transforms: Query<&Transform>,
+) {
+  for (target, mut blend) in qry.iter_mut() {
+    let Some(rig) = target.rig_target else {
+      continue;
+    };
+    let Some(active) = &mut blend.active else {
+      continue;
+    };
+    match &mut active.source {
+      BlendSource::Capture => {
+        let mut pose = Vec::new();
+        for bone in children.iter_descendants(rig) {
+          if let Ok(transform) = transforms.get(bone) {
+            pose.push((bone, *transform));
+          }
+        }
+        active.source = BlendSource::Pose(pose);
+      }
+      BlendSource::Clip { bones, .. } if bones.is_empty() => {
+        rig_bones(rig, &children, &names, bones);
+      }
+      _ => {}
+    }
+  }
+}
+
+/// Collects the named descendants of `rig` with the [`EntityPath`] clips address them by.
+pub(crate) fn rig_bones(
+  rig: Entity,
+  children: &Query<&Children>,
+  names: &Query<&Name>,
+  bones: &mut Vec<(Entity, EntityPath)>,
+) {
+  let Ok(root_name) = names.get(rig) else {
+    return;
+  };
+  let mut path = vec![root_name.clone()];
+  collect_bone_paths(rig, &mut path, children, names, bones);
+}
+
+fn collect_bone_paths(
+  entity: Entity,
+  path: &mut Vec<Name>,
+  children: &Query<&Children>,
+  names: &Query<&Name>,
+  bones: &mut Vec<(Entity, EntityPath)>,
+) {
+  let Ok(entity_children) = children.get(entity) else {
+    return;
+  };
+  for child in entity_children.iter() {
+    let Ok(name) = names.get(*child) else {
+      continue;
+    };
+    path.push(name.clone());
+    bones.push((
+      *child,
+      EntityPath {
+        parts: path.clone(),
+      },
+    ));
+    collect_bone_paths(*child, path, children, names, bones);
+    path.pop();
+  }
+}
+
+pub(crate) fn apply_blends(
+  mut qry: Query<(&mut AnimationBlend, Option<&AnimationLod>)>,
+  mut transforms: Query<&mut Transform>,
+  clips: Res<Assets<AnimationClip>>,
+  time: Res<Time>,
+) {
+  let delta = time.delta_seconds();
+  for (mut blend, lod) in qry.iter_mut() {
+    let Some(active) = &mut blend.active else {
+      continue;
+    };
+    active.elapsed += delta;
+    let progress = active.elapsed / active.blend.duration_seconds;
+    let weight = active.blend.curve.sample(progress);
+    if !is_evaluated(lod) {
+      if progress >= 1.0 {
+        blend.active = None;
+      }
+      continue;
+    }
+
+    if let (BlendMode::Inertialized, BlendSource::Pose(pose)) =
+      (active.blend.mode, &mut active.source)
+    {
+      // the first frame of the new clip turns the captured pose into an offset from it
+      let mut offsets = std::mem::take(pose);
+      for (bone, offset) in offsets.iter_mut() {
+        if let Ok(transform) = transforms.get(*bone) {
+          let mut rotation = offset.rotation * transform.rotation.inverse();
+          if rotation.w < 0.0 {
+            rotation = -rotation;
+          }
+          *offset = Transform {
+            translation: offset.translation - transform.translation,
+            rotation,
+            scale: offset.scale - transform.scale,
+          };
+        }
+      }
+      active.source = BlendSource::Offset(offsets);
+    }
+
+    match &mut active.source {
+      BlendSource::Capture => {}
+      BlendSource::Pose(pose) => {
+        for (bone, from) in pose.iter() {
+          if let Ok(mut transform) = transforms.get_mut(*bone) {
+            *transform = lerp_transform(from, &transform, weight);
+          }
+        }
+      }
+      BlendSource::Offset(offsets) => {
+        let remaining = 1.0 - weight;
+        for (bone, offset) in offsets.iter() {
+          if let Ok(mut transform) = transforms.get_mut(*bone) {
+            transform.translation += offset.translation * remaining;
+            transform.rotation =
+              (Quat::IDENTITY.slerp(offset.rotation, remaining) * transform.rotation).normalize();
+            transform.scale += offset.scale * remaining;
+          }
+        }
+      }
+      BlendSource::Clip {
+        clip,
+        seek_time,
+        speed,
+        repeat,
+        bones,
+      } => {
+        let Some(clip) = clips.get(clip.id()) else {
+          continue;
+        };
+        *seek_time += delta * *speed;
+        if *repeat && clip.duration() > 0.0 {
+          *seek_time = seek_time.rem_euclid(clip.duration());
+        } else {
+          *seek_time = seek_time.clamp(0.0, clip.duration());
+        }
+        for (bone, path) in bones.iter() {
+          let Some(curves) = clip.get_curves_by_path(path) else {
+            continue;
+          };
+          let Ok(mut transform) = transforms.get_mut(*bone) else {
+            continue;
+          };
+          let mut from = *transform;
+          for curve in curves {
+            sample_curve(curve, *seek_time, &mut from);
+          }
+          *transform = lerp_transform(&from, &transform, weight);
+        }
+      }
+    }
+
+    if progress >= 1.0 {
+      blend.active = None;
+    }
+  }
+}
+
+pub(crate) fn lerp_transform(from: &Transform, to: &Transform, weight: f32) -> Transform {
+  Transform {
+    translation: from.translation.lerp(to.translation, weight),
+    rotation: from.rotation.slerp(to.rotation, weight),
+    scale: from.scale.lerp(to.scale, weight),
+  }
+}
+
+pub(crate) fn sample_curve(curve: &VariableCurve, time: f32, transform: &mut Transform) {
+  let timestamps = &curve.keyframe_timestamps;
+  if timestamps.is_empty() {
+    return;
+  }
+  let (start, end, lerp) = match timestamps.iter().position(|t| *t > time) {
+    Some(0) => (0, 0, 0.0),
+    None => (timestamps.len() - 1, timestamps.len() - 1, 0.0),
+    Some(next) => {
+      let (t0, t1) = (timestamps[next - 1], timestamps[next]);
+      (next - 1, next, (time - t0) / (t1 - t0))
+    }
+  };
+  match &curve.keyframes {
+    Keyframes::Rotation(keys) => {
+      let mut to = keys[end];
+      if to.dot(keys[start]) < 0.0 {
+        to = -to;
+      }
+      transform.rotation = keys[start].normalize().slerp(to.normalize(), lerp);
+    }
+    Keyframes::Translation(keys) => {
+      transform.translation = keys[start].lerp(keys[end], lerp);
+    }
+    Keyframes::Scale(keys) => {
+      transform.scale = keys[start].lerp(keys[end], lerp);
+    }
+    Keyframes::Weights(_) => {}
+  }
+}
@@ -0,0 +1,109 @@
+use assets::RonAssetLoaderError;
+use bevy::{
+  asset::{AssetPath, ErasedLoadedAsset, LoadContext},
+  prelude::*,
+  reflect::Struct,
+};
+use std::collections::HashMap;
+
+use crate::{Mirror, RetargetMap};
+
+/// Loads the clips of an animation controller. Clips that have to be retargeted or mirrored are
+/// generated once and stored as labeled assets of the controller.
+pub(crate) struct ClipLoader {
+  retarget: Option<RetargetMap>,
+  mirror: Mirror,
+  files: HashMap<AssetPath<'static>, ErasedLoadedAsset>,
+  generated: HashMap<(AssetPath<'static>, bool), Handle<AnimationClip>>,
+}
+
+impl ClipLoader {
+  pub(crate) async fn new(
+    load_context: &mut LoadContext<'_>,
+    retarget: Option<&str>,
+    mirror: Mirror,
+  ) -> Result<Self, RonAssetLoaderError> {
+    let retarget = match retarget {
+      Some(path) => {
+        let loaded = load_context.load_direct(AssetPath::parse(path).into_owned());
+        let Some(map) = loaded.await?.take::<RetargetMap>() else {
+          return Err(RonAssetLoaderError::Invalid(format!(
+            "{path:?} is not a retarget map"
+          )));
+        };
+        Some(map)
+      }
+      None => None,
+    };
+    Ok(Self {
+      retarget,
+      mirror,
+      files: HashMap::new(),
+      generated: HashMap::new(),
+    })
+  }
+
+  /// Makes a file that was already loaded directly available for reading clips from.
+  pub(crate) fn add_file(&mut self, path: AssetPath<'static>, loaded: ErasedLoadedAsset) {
+    self.files.insert(path, loaded);
+  }
+
+  pub(crate) async fn load(
+    &mut self,
+    load_context: &mut LoadContext<'_>,
+    path: AssetPath<'static>,
+    mirrored: bool,
+  ) -> Result<Handle<AnimationClip>, RonAssetLoaderError> {
+    if self.retarget.is_none() && !mirrored {
+      return Ok(load_context.load(path));
+    }
+    let key = (path, mirrored);
+    if let Some(handle) = self.generated.get(&key) {
+      return Ok(handle.clone());
+    }
+    let (path, _) = &key;
+    let file = path.without_label().into_owned();
+    if !self.files.contains_key(&file) {
+      let loaded = load_context.load_direct(file.clone()).await?;
+      self.files.insert(file.clone(), loaded);
+    }
+    let loaded = &self.files[&file];
+    let clip = match path.label() {
+      Some(label) => loaded.get_labeled(label.to_owned()),
+      None => Some(loaded),
+    }
+    .and_then(|loaded| loaded.get::<AnimationClip>())
+    .ok_or_else(|| RonAssetLoaderError::Invalid(format!("{path} is not an animation clip")))?;
+
+    let retargeted = self.retarget.as_ref().map(|map| map.retarget(clip));
+    let clip = retargeted.as_ref().unwrap_or(clip);
+    let index = self.generated.len();
+    let handle = if mirrored {
+      load_context.add_labeled_asset(format!("Mirrored{index}"), self.mirror.mirror(clip))
+    } else {
+      load_context.add_labeled_asset(format!("Retargeted{index}"), clip.clone())
+    };
+    self.generated.insert(key, handle.clone());
+    Ok(handle)
+  }
+
+  /// The paths of the clips generated clips were made from, by generated clip.
+  pub(crate) fn sources(&self) -> HashMap<AssetId<AnimationClip>, AssetPath<'static>> {
+    let generated = self.generated.iter();
+    generated
+      .map(|((path, _), handle)| (handle.id(), path.clone()))
+      .collect()
+  }
+}
+
+pub(crate) fn clip_curves(
+  clip: &AnimationClip,
+) -> impl Iterator<Item = (&EntityPath, &Vec<VariableCurve>)> {
+  // the paths of a clip are only reachable through reflection
+  clip
+    .field("paths")
+    .and_then(|paths| paths.downcast_ref::<bevy::utils::HashMap<EntityPath, usize>>())
+    .into_iter()
+    .flatten()
+    .filter_map(|(path, index)| Some((path, clip.get_curves(*index)?)))
+}
@@ -1,7 +1,10 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 use std::ops::Deref;
 
-use crate::{AnimationControllerData, AnimationControllerInput, Animator, AnimatorTarget};
+use crate::{
+  AdditiveLayer, AnimationBlend, AnimationControllerData, AnimationControllerInput, Animator,
+  AnimatorOf, AnimatorTarget, PropertyPlayer,
+};
 
 pub trait AnimationController: Asset + Send {
   type ControllerData: Send + Sync;
@@ -10,29 +13,72 @@ pub trait AnimationController: Asset + Send {
     parameters: &AnimationControllerInput,
     trigger: Option<&str>,
     data: &mut Self::ControllerData,
-    player: &mut AnimationPlayer,
+    output: &mut AnimationOutput,
   );
+  /// Called when the controller asset was modified, e.g. by hot reload, with data that may refer
+  /// to states that no longer exist. Re-evaluates the controller by default.
+  fn reloaded(
+    &self,
+    parameters: &AnimationControllerInput,
+    data: &mut Self::ControllerData,
+    output: &mut AnimationOutput,
+  ) {
+    self.update_animation(parameters, None, data, output);
+  }
+  fn additive_layers(&self) -> &[AdditiveLayer] {
+    &[]
+  }
+}
+
+/// Everything a controller drives on an animated entity.
+pub struct AnimationOutput<'a> {
+  pub player: &'a mut AnimationPlayer,
+  pub blend: &'a mut AnimationBlend,
+  pub properties: &'a mut PropertyPlayer,
+}
+
+impl<'a> AnimationOutput<'a> {
+  /// Output for an animator that may lack the components of an [`crate::AnimatedBundle`]. Without
+  /// an [`AnimationBlend`] clips are switched without blending and without a [`PropertyPlayer`]
+  /// property tracks are not played, the missing components are stood in for by `fallback`.
+  pub(crate) fn new(
+    player: &'a mut AnimationPlayer,
+    blend: Option<Mut<'a, AnimationBlend>>,
+    properties: Option<Mut<'a, PropertyPlayer>>,
+    fallback: &'a mut (AnimationBlend, PropertyPlayer),
+  ) -> Self {
+    AnimationOutput {
+      player,
+      blend: blend.map_or(&mut fallback.0, Mut::into_inner),
+      properties: properties.map_or(&mut fallback.1, Mut::into_inner),
+    }
+  }
 }
 
 pub fn find_rig_target<T: AnimationController>(
   mut cmd: Commands,
-  mut qry: Query<
-    (Entity, &Animator<T>),
-    Or<(
-      Changed<Children>,
-      Without<AnimatorTarget>,
-      Changed<Animator<T>>,
-    )>,
-  >,
+  qry: Query<(
+    Entity,
+    Ref<Animator<T>>,
+    Option<Ref<AnimatorOf>>,
+    Has<AnimatorTarget>,
+  )>,
   mut targets: Query<&mut AnimatorTarget>,
+  changed_children: Query<(), Changed<Children>>,
   children: Query<&Children>,
   names: Query<&Name>,
 ) {
-  for (e, animator) in qry.iter_mut() {
+  for (e, animator, animator_of, has_target) in qry.iter() {
+    // the rig path of one of several animators starts at the entity they animate
+    let root = animator_of.as_ref().map_or(e, |a| a.owner);
+    let changed = animator.is_changed() || animator_of.is_some_and(|a| a.is_changed());
+    if has_target && !changed && !changed_children.contains(root) {
+      continue;
+    }
     let rig = if let Some(path) = &animator.rig_path {
       let mut cache = Vec::new();
 
-      entity_from_path2(e, path, &children, &names, &mut cache)
+      entity_from_path2(root, path, &children, &names, &mut cache)
     } else {
       None
     };
@@ -46,34 +92,88 @@ pub fn find_rig_target<T: AnimationController>(
 }
 
 pub fn play_animations<T: AnimationController + Asset>(
-  mut controllers: ResMut<Assets<T>>,
+  controllers: Res<Assets<T>>,
   mut qry: Query<
     (
       &AnimatorTarget,
       &Animator<T>,
       &AnimationControllerInput,
       &mut AnimationControllerData<T>,
+      Option<&mut AnimationBlend>,
+      Option<&mut PropertyPlayer>,
     ),
     Changed<AnimationControllerInput>,
   >,
   mut qry_player: Query<&mut AnimationPlayer>,
 ) {
-  for (target, animator, params, mut data) in qry.iter_mut() {
+  for (target, animator, params, mut data, blend, properties) in qry.iter_mut() {
+    let Some(rig_target) = target.rig_target else {
+      continue;
+    };
+    let Ok(mut player) = qry_player.get_mut(rig_target) else {
+      continue;
+    };
+    let Some(controller) = controllers.get(&animator.controller) else {
+      continue;
+    };
+
+    let mut fallback = Default::default();
+    let mut output = AnimationOutput::new(&mut player, blend, properties, &mut fallback);
+    controller.update_animation(params, None, &mut data.data, &mut output);
+  }
+}
+
+/// Lets animators of modified controllers catch up with the new version.
+pub fn reload_controllers<T: AnimationController + Asset>(
+  mut events: EventReader<AssetEvent<T>>,
+  controllers: Res<Assets<T>>,
+  qry: Query<(
+    Entity,
+    &AnimatorTarget,
+    &Animator<T>,
+    &AnimationControllerInput,
+  )>,
+  mut outputs: Query<(
+    &mut AnimationControllerData<T>,
+    Option<&mut AnimationBlend>,
+    Option<&mut PropertyPlayer>,
+  )>,
+  mut qry_player: Query<&mut AnimationPlayer>,
+) {
+  let modified: HashSet<_> = events
+    .read()
+    .filter_map(|event| match event {
+      AssetEvent::Modified { id } => Some(*id),
+      _ => None,
+    })
+    .collect();
+  if modified.is_empty() {
+    return;
+  }
+  for (entity, target, animator, params) in qry.iter() {
+    if !modified.contains(&animator.controller.id()) {
+      continue;
+    }
     let Some(rig_target) = target.rig_target else {
       continue;
     };
+    let Ok((mut data, blend, properties)) = outputs.get_mut(entity) else {
+      continue;
+    };
     let Ok(mut player) = qry_player.get_mut(rig_target) else {
       continue;
     };
-    let Some(controller) = controllers.get_mut(animator.controller.clone()) else {
+    let Some(controller) = controllers.get(&animator.controller) else {
       continue;
     };
 
-    controller.update_animation(params, None, &mut data.data, &mut player);
+    let mut fallback = Default::default();
+    let mut output = AnimationOutput::new(&mut player, blend, properties, &mut fallback);
+    controller.reloaded(params, &mut data.data, &mut output);
   }
 }
 
-fn entity_from_path2(
+pub(crate) fn entity_from_path2(
   root: Entity,
   path: &EntityPath,
   children: &Query<&Children>,
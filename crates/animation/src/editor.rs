@@ -0,0 +1,674 @@
+use bevy::{
+  asset::{io::file::FileAssetReader, AssetPath},
+  prelude::*,
+};
+use egui::{
+  Align2, Color32, ComboBox, DragValue, FontId, Id, Pos2, Rect, Rounding, Sense, Stroke, TextEdit,
+  Ui, Vec2,
+};
+use serde_ron::ser::PrettyConfig;
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  basic_controller::{
+    BasicAnimationNode, BasicAnimationTransition, BasicAnimationTransitionCondition, BasicNodeId,
+  },
+  AnimationControllerData, AnimationControllerInput, Animator, BasicAnimationController,
+  BlendCurve, BlendMode, ConditionExpr,
+};
+
+const NODE_SIZE: Vec2 = Vec2::new(140.0, 44.0);
+
+/// State of the animation graph editor window.
+#[derive(Resource)]
+pub struct AnimationGraphEditor {
+  /// Directory of the default asset source, documents are read from and saved to it
+  root: PathBuf,
+  document: Option<Document>,
+  preview: Option<Entity>,
+  new_node: String,
+  new_parameter: String,
+  status: String,
+}
+
+impl AnimationGraphEditor {
+  pub fn new(root: impl Into<PathBuf>) -> Self {
+    Self {
+      root: root.into(),
+      document: None,
+      preview: None,
+      new_node: String::new(),
+      new_parameter: String::new(),
+      status: String::new(),
+    }
+  }
+}
+
+impl Default for AnimationGraphEditor {
+  fn default() -> Self {
+    Self::new(asset_root(&AssetPlugin::default()))
+  }
+}
+
+/// Directory the default asset source of `plugin` reads files from.
+pub(crate) fn asset_root(plugin: &AssetPlugin) -> PathBuf {
+  FileAssetReader::get_base_path().join(&plugin.file_path)
+}
+
+/// A controller document as written on disk, without its bases merged in.
+struct Document {
+  path: AssetPath<'static>,
+  file: PathBuf,
+  controller: BasicAnimationController,
+  selected: Option<BasicNodeId>,
+  dirty: bool,
+}
+
+impl Document {
+  fn open(root: &Path, path: AssetPath<'static>) -> Result<Self, String> {
+    let file = root.join(path.path());
+    let bytes = std::fs::read(&file).map_err(|e| e.to_string())?;
+    let controller = serde_ron::de::from_bytes(&bytes).map_err(|e| e.to_string())?;
+    Ok(Self {
+      path,
+      file,
+      controller,
+      selected: None,
+      dirty: false,
+    })
+  }
+
+  /// Writes the document back to its file, the asset server reloads it when watching for changes.
+  fn save(&mut self) -> Result<(), String> {
+    let config = PrettyConfig::default().indentor("  ".to_owned());
+    let ron =
+      serde_ron::ser::to_string_pretty(&self.controller, config).map_err(|e| e.to_string())?;
+    std::fs::write(&self.file, ron).map_err(|e| e.to_string())?;
+    self.dirty = false;
+    Ok(())
+  }
+}
+
+/// Shows the graph editor for [`BasicAnimationController`] assets in its own window.
+pub fn animation_graph_editor(world: &mut World, ctx: &egui::Context) {
+  if !world.contains_resource::<AnimationGraphEditor>() {
+    world.init_resource::<AnimationGraphEditor>();
+  }
+  world.resource_scope(|world, mut editor: Mut<AnimationGraphEditor>| {
+    egui::Window::new("Animation graph")
+      .default_size([760.0, 480.0])
+      .show(ctx, |ui| editor.ui(world, ui));
+  });
+}
+
+impl AnimationGraphEditor {
+  fn ui(&mut self, world: &mut World, ui: &mut Ui) {
+    let Self {
+      root,
+      document,
+      preview,
+      new_node,
+      new_parameter,
+      status,
+    } = self;
+
+    ui.horizontal(|ui| {
+      if let Some(path) = open_menu(world, ui, document.as_ref().map(|d| &d.path)) {
+        match Document::open(root, path) {
+          Ok(opened) => {
+            *status = String::new();
+            *document = Some(opened);
+          }
+          Err(error) => *status = error,
+        }
+      }
+      if let Some(doc) = document {
+        if ui
+          .add_enabled(doc.dirty, egui::Button::new("Save"))
+          .clicked()
+        {
+          *status = match doc.save() {
+            Ok(()) => format!("Saved {}", doc.path),
+            Err(error) => error,
+          };
+        }
+        if ui
+          .add_enabled(doc.dirty, egui::Button::new("Revert"))
+          .clicked()
+        {
+          match Document::open(root, doc.path.clone()) {
+            Ok(opened) => *doc = opened,
+            Err(error) => *status = error,
+          }
+        }
+      }
+      ui.label(status.as_str());
+    });
+    let Some(doc) = document else {
+      ui.label("Open a controller to edit it");
+      return;
+    };
+    ui.separator();
+
+    let mut active = None;
+    egui::SidePanel::right("animation_graph_inspector")
+      .default_width(280.0)
+      .show_inside(ui, |ui| {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+          active = preview_ui(world, ui, doc, preview, new_parameter);
+          ui.separator();
+          inspector_ui(ui, doc);
+        });
+      });
+    egui::CentralPanel::default().show_inside(ui, |ui| {
+      ui.horizontal(|ui| {
+        ui.add(TextEdit::singleline(new_node).desired_width(120.0));
+        let id = BasicNodeId(new_node.trim().to_owned().into());
+        let valid = !new_node.trim().is_empty() && !doc.controller.nodes.contains_key(&id);
+        if ui
+          .add_enabled(valid, egui::Button::new("Add node"))
+          .clicked()
+        {
+          doc.controller.nodes.insert(
+            id.clone(),
+            BasicAnimationNode {
+              animation: None,
+              clip: None,
+              properties: None,
+              mirror: false,
+              repeat: true,
+              speed: 1.0,
+            },
+          );
+          doc.selected = Some(id);
+          doc.dirty = true;
+          new_node.clear();
+        }
+      });
+      ui.label("Drag nodes to move them, shift+click a node to connect the selected node to it");
+      graph_ui(ui, doc, active.as_ref());
+    });
+  }
+}
+
+fn open_menu(
+  world: &World,
+  ui: &mut Ui,
+  current: Option<&AssetPath<'static>>,
+) -> Option<AssetPath<'static>> {
+  let server = world.resource::<AssetServer>();
+  let mut paths: Vec<_> = world
+    .resource::<Assets<BasicAnimationController>>()
+    .ids()
+    .filter_map(|id| server.get_path(id).map(|p| p.into_owned()))
+    .collect();
+  paths.sort_by_key(|p| p.to_string());
+
+  let mut open = None;
+  ComboBox::from_id_source("animation_graph_open")
+    .width(240.0)
+    .selected_text(current.map_or("Open controller".to_owned(), |p| p.to_string()))
+    .show_ui(ui, |ui| {
+      for path in paths {
+        if ui
+          .selectable_label(current == Some(&path), path.to_string())
+          .clicked()
+        {
+          open = Some(path);
+        }
+      }
+    });
+  open
+}
+
+/// Lets parameters of an animated entity using the document be changed, returns its active node.
+fn preview_ui(
+  world: &mut World,
+  ui: &mut Ui,
+  doc: &Document,
+  preview: &mut Option<Entity>,
+  new_parameter: &mut String,
+) -> Option<BasicNodeId> {
+  let mut animators = world.query::<(Entity, &Animator<BasicAnimationController>, Option<&Name>)>();
+  let server = world.resource::<AssetServer>();
+  let candidates: Vec<_> = animators
+    .iter(world)
+    .filter(|(_, animator, _)| {
+      server.get_path(animator.controller.id()).as_ref() == Some(&doc.path)
+    })
+    .map(|(entity, _, name)| {
+      (
+        entity,
+        name.map_or_else(|| format!("{entity:?}"), |n| n.to_string()),
+      )
+    })
+    .collect();
+  if !candidates.iter().any(|(e, _)| Some(*e) == *preview) {
+    *preview = candidates.first().map(|(e, _)| *e);
+  }
+
+  ui.heading("Preview");
+  let Some(entity) = *preview else {
+    ui.label("No animated entity uses this controller");
+    return None;
+  };
+  let selected = candidates
+    .iter()
+    .find(|(e, _)| *e == entity)
+    .map(|(_, name)| name.clone())
+    .unwrap_or_default();
+  ComboBox::from_id_source("animation_graph_preview")
+    .selected_text(selected)
+    .show_ui(ui, |ui| {
+      for (candidate, name) in candidates.iter() {
+        ui.selectable_value(preview, Some(*candidate), name);
+      }
+    });
+
+  let active = world
+    .get::<AnimationControllerData<BasicAnimationController>>(entity)
+    .and_then(|data| data.data.active_node.clone());
+  ui.label(format!(
+    "Active node: {}",
+    active.as_ref().map_or("none", |id| id.0.as_str())
+  ));
+
+  let Some(mut input) = world.get_mut::<AnimationControllerInput>(entity) else {
+    return active;
+  };
+  let mut names: Vec<String> = input.parameters.keys().cloned().collect();
+  for edge in doc.controller.edges.iter() {
+    for condition in edge.conditions.iter() {
+      match condition {
+        BasicAnimationTransitionCondition::GreaterThan(name, _)
+        | BasicAnimationTransitionCondition::LessThan(name, _) => names.push(name.clone()),
+        BasicAnimationTransitionCondition::Expression(expr) => {
+          names.extend(expr.parameters().into_iter().map(str::to_owned))
+        }
+        BasicAnimationTransitionCondition::Trigger(_) => {}
+      }
+    }
+  }
+  names.sort();
+  names.dedup();
+  for name in names {
+    let mut value = input.get_parameter(&name);
+    let label = format!("{name}: ");
+    if ui
+      .add(DragValue::new(&mut value).speed(0.05).prefix(label))
+      .changed()
+    {
+      input.parameters.insert(name, value);
+    }
+  }
+  ui.horizontal(|ui| {
+    ui.add(TextEdit::singleline(new_parameter).desired_width(120.0));
+    if ui.button("Add parameter").clicked() && !new_parameter.is_empty() {
+      input.parameters.insert(std::mem::take(new_parameter), 0.0);
+    }
+  });
+  active
+}
+
+fn inspector_ui(ui: &mut Ui, doc: &mut Document) {
+  let Some(id) = doc.selected.clone() else {
+    ui.heading("Transitions from any node");
+    doc.dirty |= edges_ui(ui, doc, None);
+    return;
+  };
+  let Some(node) = doc.controller.nodes.get_mut(&id) else {
+    doc.selected = None;
+    return;
+  };
+
+  ui.heading(id.0.as_str());
+  let mut changed = false;
+  changed |= optional_text(ui, "Clip", &mut node.clip);
+  changed |= optional_text(ui, "Animation", &mut node.animation);
+  changed |= optional_text(ui, "Properties", &mut node.properties);
+  ui.horizontal(|ui| {
+    ui.label("Speed");
+    changed |= ui
+      .add(DragValue::new(&mut node.speed).speed(0.05))
+      .changed();
+    changed |= ui.checkbox(&mut node.repeat, "Repeat").changed();
+    changed |= ui.checkbox(&mut node.mirror, "Mirror").changed();
+  });
+  ui.horizontal(|ui| {
+    let is_default = doc.controller.default_node.as_ref() == Some(&id);
+    if ui
+      .add_enabled(!is_default, egui::Button::new("Make default"))
+      .clicked()
+    {
+      doc.controller.default_node = Some(id.clone());
+      changed = true;
+    }
+    if ui.button("Delete node").clicked() {
+      let controller = &mut doc.controller;
+      controller.nodes.remove(&id);
+      controller.layout.remove(&id);
+      controller
+        .edges
+        .retain(|e| e.to != id && e.from.as_ref() != Some(&id));
+      if controller.default_node.as_ref() == Some(&id) {
+        controller.default_node = None;
+      }
+      doc.selected = None;
+      changed = true;
+    }
+  });
+  doc.dirty |= changed;
+  if doc.selected.is_none() {
+    return;
+  }
+
+  ui.separator();
+  ui.heading("Transitions");
+  doc.dirty |= edges_ui(ui, doc, Some(&id));
+}
+
+fn edges_ui(ui: &mut Ui, doc: &mut Document, from: Option<&BasicNodeId>) -> bool {
+  let mut changed = false;
+  let mut remove = None;
+  for (index, edge) in doc.controller.edges.iter_mut().enumerate() {
+    if edge.from.as_ref() != from {
+      continue;
+    }
+    egui::CollapsingHeader::new(format!("to {}", edge.to.0))
+      .id_source(("animation_graph_edge", index))
+      .show(ui, |ui| {
+        changed |= edge_ui(ui, index, edge);
+        if ui.button("Remove transition").clicked() {
+          remove = Some(index);
+        }
+      });
+  }
+  if let Some(index) = remove {
+    doc.controller.edges.remove(index);
+    changed = true;
+  }
+  changed
+}
+
+fn edge_ui(ui: &mut Ui, index: usize, edge: &mut BasicAnimationTransition) -> bool {
+  let mut changed = false;
+  ui.horizontal(|ui| {
+    ui.label("Duration");
+    changed |= ui
+      .add(
+        DragValue::new(&mut edge.transition_duration_seconds)
+          .speed(0.01)
+          .clamp_range(0.0..=10.0)
+          .suffix(" s"),
+      )
+      .changed();
+    changed |= ui.checkbox(&mut edge.enabled, "Enabled").changed();
+  });
+  ui.horizontal(|ui| {
+    ComboBox::from_id_source(("animation_graph_mode", index))
+      .selected_text(format!("{:?}", edge.mode))
+      .show_ui(ui, |ui| {
+        for mode in [
+          BlendMode::Crossfade,
+          BlendMode::Frozen,
+          BlendMode::Inertialized,
+        ] {
+          changed |= ui
+            .selectable_value(&mut edge.mode, mode, format!("{mode:?}"))
+            .changed();
+        }
+      });
+    ComboBox::from_id_source(("animation_graph_curve", index))
+      .selected_text(format!("{:?}", edge.curve))
+      .show_ui(ui, |ui| {
+        for curve in [
+          BlendCurve::Linear,
+          BlendCurve::EaseIn,
+          BlendCurve::EaseOut,
+          BlendCurve::EaseInOut,
+        ] {
+          let label = format!("{curve:?}");
+          changed |= ui.selectable_value(&mut edge.curve, curve, label).changed();
+        }
+      });
+  });
+
+  let mut remove = None;
+  for (i, condition) in edge.conditions.iter_mut().enumerate() {
+    ui.horizontal(|ui| {
+      changed |= condition_ui(
+        ui,
+        Id::new(("animation_graph_condition", index, i)),
+        condition,
+      );
+      if ui.small_button("x").clicked() {
+        remove = Some(i);
+      }
+    });
+  }
+  if let Some(i) = remove {
+    edge.conditions.remove(i);
+    changed = true;
+  }
+  if ui.button("Add condition").clicked() {
+    edge
+      .conditions
+      .push(BasicAnimationTransitionCondition::GreaterThan(
+        String::new(),
+        0.0,
+      ));
+    changed = true;
+  }
+  changed
+}
+
+fn condition_ui(ui: &mut Ui, id: Id, condition: &mut BasicAnimationTransitionCondition) -> bool {
+  use BasicAnimationTransitionCondition::*;
+  let mut changed = false;
+  let kind = match condition {
+    GreaterThan(..) => "GreaterThan",
+    LessThan(..) => "LessThan",
+    Trigger(..) => "Trigger",
+    Expression(..) => "Expression",
+  };
+  ComboBox::from_id_source(id.with("kind"))
+    .width(100.0)
+    .selected_text(kind)
+    .show_ui(ui, |ui| {
+      for other in ["GreaterThan", "LessThan", "Trigger", "Expression"] {
+        if ui.selectable_label(other == kind, other).clicked() && other != kind {
+          let text = match condition {
+            GreaterThan(name, _) | LessThan(name, _) | Trigger(name) => name.clone(),
+            Expression(expr) => expr.source().to_owned(),
+          };
+          *condition = match other {
+            "GreaterThan" => GreaterThan(text, 0.0),
+            "LessThan" => LessThan(text, 0.0),
+            "Trigger" => Trigger(text),
+            _ => Expression(
+              ConditionExpr::parse(&text)
+                .or_else(|_| ConditionExpr::parse("false"))
+                .expect("literal expression parses"),
+            ),
+          };
+          ui.data_mut(|d| d.remove::<String>(id));
+          changed = true;
+        }
+      }
+    });
+  match condition {
+    GreaterThan(name, value) | LessThan(name, value) => {
+      changed |= ui
+        .add(TextEdit::singleline(name).desired_width(90.0))
+        .changed();
+      changed |= ui.add(DragValue::new(value).speed(0.05)).changed();
+    }
+    Trigger(name) => {
+      changed |= ui
+        .add(TextEdit::singleline(name).desired_width(90.0))
+        .changed();
+    }
+    Expression(expr) => {
+      // the text being typed is kept until it parses
+      let mut source = ui
+        .data_mut(|d| d.get_temp::<String>(id))
+        .unwrap_or_else(|| expr.source().to_owned());
+      let response = ui.add(TextEdit::singleline(&mut source).desired_width(160.0));
+      match ConditionExpr::parse(&source) {
+        Ok(parsed) if response.changed() => {
+          *expr = parsed;
+          changed = true;
+        }
+        Ok(_) => {}
+        Err(error) => {
+          ui.colored_label(Color32::RED, "!")
+            .on_hover_text(error.to_string());
+        }
+      }
+      ui.data_mut(|d| d.insert_temp(id, source));
+    }
+  }
+  changed
+}
+
+/// Text field for an optional string, empty meaning `None`.
+fn optional_text(ui: &mut Ui, label: &str, value: &mut Option<String>) -> bool {
+  let mut text = value.clone().unwrap_or_default();
+  let changed = ui
+    .horizontal(|ui| {
+      ui.label(label);
+      ui.text_edit_singleline(&mut text).changed()
+    })
+    .inner;
+  if changed {
+    *value = (!text.is_empty()).then_some(text);
+  }
+  changed
+}
+
+fn graph_ui(ui: &mut Ui, doc: &mut Document, active: Option<&BasicNodeId>) {
+  let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::hover());
+  let origin = response.rect.min.to_vec2();
+  let any = response.rect.min + Vec2::new(30.0, 16.0);
+
+  let mut ids: Vec<_> = doc.controller.nodes.keys().cloned().collect();
+  ids.sort();
+  // nodes without a stored position are laid out on a grid
+  let positions: HashMap<_, _> = ids
+    .iter()
+    .enumerate()
+    .map(|(i, id)| {
+      let (x, y) = doc.controller.layout.get(id).copied().unwrap_or((
+        (i % 4) as f32 * (NODE_SIZE.x + 30.0) + 20.0,
+        (i / 4) as f32 * (NODE_SIZE.y + 50.0) + 50.0,
+      ));
+      (id.clone(), Pos2::new(x, y) + origin)
+    })
+    .collect();
+  let node_rect = |id: &BasicNodeId| {
+    positions
+      .get(id)
+      .map(|p| Rect::from_min_size(*p, NODE_SIZE))
+  };
+
+  for edge in doc.controller.edges.iter() {
+    let Some(to) = node_rect(&edge.to) else {
+      continue;
+    };
+    let from = match &edge.from {
+      Some(from) => match node_rect(from) {
+        Some(rect) => Some(rect),
+        None => continue,
+      },
+      None => None,
+    };
+    let start = from.map_or(any, |r| r.center());
+    let Some(dir) = Some(to.center() - start).filter(|d| d.length() > 1.0) else {
+      continue;
+    };
+    let dir = dir.normalized();
+    // keep transitions in both directions apart
+    let side = Vec2::new(-dir.y, dir.x) * 6.0;
+    let inset = |rect: Rect| {
+      let half = rect.size() / 2.0;
+      (half.x / dir.x.abs()).min(half.y / dir.y.abs())
+    };
+    let start = start + side + dir * from.map_or(0.0, inset);
+    let end = to.center() + side - dir * inset(to);
+    let color = if edge.enabled {
+      Color32::LIGHT_GRAY
+    } else {
+      Color32::DARK_GRAY
+    };
+    painter.arrow(start, end - start, Stroke::new(2.0, color));
+  }
+  painter.text(
+    any,
+    Align2::CENTER_CENTER,
+    "any",
+    FontId::proportional(13.0),
+    Color32::GRAY,
+  );
+
+  let connect = ui.input(|i| i.modifiers.shift);
+  for id in ids {
+    let Some(rect) = node_rect(&id) else {
+      continue;
+    };
+    let node = ui.interact(
+      rect,
+      Id::new(("animation_graph_node", id.0.as_str())),
+      Sense::click_and_drag(),
+    );
+    if node.dragged() {
+      let position = rect.min + node.drag_delta() - origin;
+      doc
+        .controller
+        .layout
+        .insert(id.clone(), (position.x, position.y));
+      doc.dirty = true;
+    }
+    if node.clicked() {
+      match doc.selected.clone() {
+        Some(from) if connect && from != id => {
+          doc.controller.edges.push(BasicAnimationTransition {
+            from: Some(from),
+            to: id.clone(),
+            transition_duration_seconds: 0.2,
+            enabled: true,
+            ..default()
+          });
+          doc.dirty = true;
+        }
+        _ => doc.selected = Some(id.clone()),
+      }
+    }
+
+    let fill = if active == Some(&id) {
+      Color32::from_rgb(40, 90, 50)
+    } else {
+      Color32::from_gray(40)
+    };
+    let stroke = if doc.selected.as_ref() == Some(&id) {
+      Stroke::new(2.0, Color32::YELLOW)
+    } else {
+      Stroke::new(1.0, Color32::GRAY)
+    };
+    painter.rect(rect, Rounding::same(4.0), fill, stroke);
+    let label = if doc.controller.default_node.as_ref() == Some(&id) {
+      format!("{} (default)", id.0)
+    } else {
+      id.0.to_string()
+    };
+    painter.text(
+      rect.center(),
+      Align2::CENTER_CENTER,
+      label,
+      FontId::proportional(14.0),
+      Color32::WHITE,
+    );
+  }
+}
@@ -0,0 +1,693 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt};
+use thiserror::Error;
+
+/// A boolean condition parsed from a string such as `velocity > 0.5 || sprint`.
+///
+/// Parsing and type checking happen during deserialization so a malformed expression fails the
+/// asset load. Evaluating a parsed expression never allocates.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct ConditionExpr {
+  source: String,
+  expr: BoolExpr,
+}
+
+impl ConditionExpr {
+  pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let ast = parser.or()?;
+    if let Some((pos, token)) = parser.tokens.get(parser.pos) {
+      return Err(ExpressionError::UnexpectedToken(*pos, token.to_string()));
+    }
+    Ok(Self {
+      source: source.to_owned(),
+      expr: boolean(&ast)?,
+    })
+  }
+
+  pub fn evaluate(&self, parameters: &HashMap<String, f32>, trigger: Option<&str>) -> bool {
+    self.expr.eval(&Context {
+      parameters,
+      trigger,
+    })
+  }
+
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  /// Names of the parameters the expression reads.
+  pub fn parameters(&self) -> Vec<&str> {
+    let mut names = Names::default();
+    self.expr.names(&mut names);
+    names.parameters
+  }
+
+  /// Names of the triggers the expression checks for.
+  pub fn triggers(&self) -> Vec<&str> {
+    let mut names = Names::default();
+    self.expr.names(&mut names);
+    names.triggers
+  }
+}
+
+impl TryFrom<String> for ConditionExpr {
+  type Error = ExpressionError;
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    Self::parse(&value)
+  }
+}
+
+impl From<ConditionExpr> for String {
+  fn from(value: ConditionExpr) -> Self {
+    value.source
+  }
+}
+
+impl fmt::Debug for ConditionExpr {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_tuple("ConditionExpr").field(&self.source).finish()
+  }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ExpressionError {
+  #[error("unexpected character {1:?} at {0}")]
+  UnexpectedChar(usize, char),
+  #[error("unexpected {1} at {0}")]
+  UnexpectedToken(usize, String),
+  #[error("unexpected end of expression")]
+  UnexpectedEnd,
+  #[error("invalid number {1:?} at {0}")]
+  InvalidNumber(usize, String),
+  #[error("unterminated string at {0}")]
+  UnterminatedString(usize),
+  #[error("expected {1} at {0}, found {2}")]
+  TypeMismatch(usize, Type, Type),
+  #[error("unknown function {1:?} at {0}")]
+  UnknownFunction(usize, String),
+  #[error("function {1} at {0} takes {2} argument(s), {3} given")]
+  WrongArity(usize, String, usize, usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Type {
+  Number,
+  Boolean,
+  String,
+}
+
+impl fmt::Display for Type {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Type::Number => write!(f, "number"),
+      Type::Boolean => write!(f, "boolean"),
+      Type::String => write!(f, "string"),
+    }
+  }
+}
+
+struct Context<'a> {
+  parameters: &'a HashMap<String, f32>,
+  trigger: Option<&'a str>,
+}
+
+impl Context<'_> {
+  fn parameter(&self, name: &str) -> f32 {
+    *self.parameters.get(name).unwrap_or(&0.0)
+  }
+}
+
+#[derive(Default)]
+struct Names<'a> {
+  parameters: Vec<&'a str>,
+  triggers: Vec<&'a str>,
+}
+
+#[derive(Clone)]
+enum BoolExpr {
+  Const(bool),
+  // a parameter used as a boolean is true when non-zero
+  Parameter(String),
+  Trigger(String),
+  Not(Box<BoolExpr>),
+  And(Box<BoolExpr>, Box<BoolExpr>),
+  Or(Box<BoolExpr>, Box<BoolExpr>),
+  Equal(Box<BoolExpr>, Box<BoolExpr>),
+  Compare(CompareOp, Box<NumExpr>, Box<NumExpr>),
+}
+
+impl BoolExpr {
+  fn names<'a>(&'a self, names: &mut Names<'a>) {
+    match self {
+      BoolExpr::Const(_) => {}
+      BoolExpr::Parameter(name) => names.parameters.push(name),
+      BoolExpr::Trigger(name) => names.triggers.push(name),
+      BoolExpr::Not(e) => e.names(names),
+      BoolExpr::And(a, b) | BoolExpr::Or(a, b) | BoolExpr::Equal(a, b) => {
+        a.names(names);
+        b.names(names);
+      }
+      BoolExpr::Compare(_, a, b) => {
+        a.names(names);
+        b.names(names);
+      }
+    }
+  }
+
+  fn eval(&self, ctx: &Context) -> bool {
+    match self {
+      BoolExpr::Const(v) => *v,
+      BoolExpr::Parameter(name) => ctx.parameter(name) != 0.0,
+      BoolExpr::Trigger(name) => ctx.trigger == Some(name.as_str()),
+      BoolExpr::Not(e) => !e.eval(ctx),
+      BoolExpr::And(a, b) => a.eval(ctx) && b.eval(ctx),
+      BoolExpr::Or(a, b) => a.eval(ctx) || b.eval(ctx),
+      BoolExpr::Equal(a, b) => a.eval(ctx) == b.eval(ctx),
+      BoolExpr::Compare(op, a, b) => {
+        let (a, b) = (a.eval(ctx), b.eval(ctx));
+        match op {
+          CompareOp::Equal => a == b,
+          CompareOp::NotEqual => a != b,
+          CompareOp::Less => a < b,
+          CompareOp::LessOrEqual => a <= b,
+          CompareOp::Greater => a > b,
+          CompareOp::GreaterOrEqual => a >= b,
+        }
+      }
+    }
+  }
+}
+
+#[derive(Clone)]
+enum NumExpr {
+  Const(f32),
+  Parameter(String),
+  Neg(Box<NumExpr>),
+  Arith(ArithOp, Box<NumExpr>, Box<NumExpr>),
+  Call(Function, Box<[NumExpr]>),
+}
+
+impl NumExpr {
+  fn names<'a>(&'a self, names: &mut Names<'a>) {
+    match self {
+      NumExpr::Const(_) => {}
+      NumExpr::Parameter(name) => names.parameters.push(name),
+      NumExpr::Neg(e) => e.names(names),
+      NumExpr::Arith(_, a, b) => {
+        a.names(names);
+        b.names(names);
+      }
+      NumExpr::Call(_, args) => args.iter().for_each(|a| a.names(names)),
+    }
+  }
+
+  fn eval(&self, ctx: &Context) -> f32 {
+    match self {
+      NumExpr::Const(v) => *v,
+      NumExpr::Parameter(name) => ctx.parameter(name),
+      NumExpr::Neg(e) => -e.eval(ctx),
+      NumExpr::Arith(op, a, b) => {
+        let (a, b) = (a.eval(ctx), b.eval(ctx));
+        match op {
+          ArithOp::Add => a + b,
+          ArithOp::Sub => a - b,
+          ArithOp::Mul => a * b,
+          ArithOp::Div => a / b,
+          ArithOp::Rem => a % b,
+        }
+      }
+      NumExpr::Call(f, args) => {
+        let arg = |i: usize| args[i].eval(ctx);
+        match f {
+          Function::Abs => arg(0).abs(),
+          Function::Sign => arg(0).signum(),
+          Function::Floor => arg(0).floor(),
+          Function::Ceil => arg(0).ceil(),
+          Function::Sqrt => arg(0).sqrt(),
+          Function::Min => arg(0).min(arg(1)),
+          Function::Max => arg(0).max(arg(1)),
+          // not `f32::clamp`, which panics on NaN bounds, the lower bound wins when they cross
+          Function::Clamp => arg(0).min(arg(2)).max(arg(1)),
+        }
+      }
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CompareOp {
+  Equal,
+  NotEqual,
+  Less,
+  LessOrEqual,
+  Greater,
+  GreaterOrEqual,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ArithOp {
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Rem,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Function {
+  Abs,
+  Sign,
+  Floor,
+  Ceil,
+  Sqrt,
+  Min,
+  Max,
+  Clamp,
+}
+
+impl Function {
+  fn from_name(name: &str) -> Option<Self> {
+    Some(match name {
+      "abs" => Function::Abs,
+      "sign" => Function::Sign,
+      "floor" => Function::Floor,
+      "ceil" => Function::Ceil,
+      "sqrt" => Function::Sqrt,
+      "min" => Function::Min,
+      "max" => Function::Max,
+      "clamp" => Function::Clamp,
+      _ => return None,
+    })
+  }
+
+  fn arity(&self) -> usize {
+    match self {
+      Function::Abs | Function::Sign | Function::Floor | Function::Ceil | Function::Sqrt => 1,
+      Function::Min | Function::Max => 2,
+      Function::Clamp => 3,
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+  Number(&'a str),
+  Ident(&'a str),
+  Str(&'a str),
+  Op(&'static str),
+}
+
+impl fmt::Display for Token<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Token::Number(v) | Token::Ident(v) => write!(f, "`{v}`"),
+      Token::Str(v) => write!(f, "'{v}'"),
+      Token::Op(v) => write!(f, "`{v}`"),
+    }
+  }
+}
+
+const OPERATORS: &[&str] = &[
+  "||", "&&", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%", "(", ")", ",",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token<'_>)>, ExpressionError> {
+  let mut tokens = Vec::new();
+  let bytes = source.as_bytes();
+  let mut i = 0;
+  while i < bytes.len() {
+    let c = bytes[i] as char;
+    if c.is_ascii_whitespace() {
+      i += 1;
+    } else if c.is_ascii_digit() || c == '.' {
+      let start = i;
+      while i < bytes.len() {
+        // the sign of an exponent, as in `1e-3`
+        let exponent_sign = matches!(bytes[i], b'+' | b'-') && matches!(bytes[i - 1], b'e' | b'E');
+        if !(bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.' || exponent_sign) {
+          break;
+        }
+        i += 1;
+      }
+      tokens.push((start, Token::Number(&source[start..i])));
+    } else if c.is_ascii_alphabetic() || c == '_' {
+      let start = i;
+      while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+        i += 1;
+      }
+      tokens.push((start, Token::Ident(&source[start..i])));
+    } else if c == '\'' || c == '"' {
+      let start = i;
+      let Some(len) = source[i + 1..].find(c) else {
+        return Err(ExpressionError::UnterminatedString(start));
+      };
+      i += len + 2;
+      tokens.push((start, Token::Str(&source[start + 1..i - 1])));
+    } else if let Some(op) = OPERATORS.iter().find(|op| source[i..].starts_with(**op)) {
+      tokens.push((i, Token::Op(op)));
+      i += op.len();
+    } else {
+      let c = source[i..].chars().next().unwrap_or(c);
+      return Err(ExpressionError::UnexpectedChar(i, c));
+    }
+  }
+  Ok(tokens)
+}
+
+enum Ast<'a> {
+  Number(f32),
+  Bool(bool),
+  Ident(&'a str),
+  Str(&'a str),
+  Unary(&'static str, Box<Node<'a>>),
+  Binary(&'static str, Box<Node<'a>>, Box<Node<'a>>),
+  Call(&'a str, Vec<Node<'a>>),
+}
+
+struct Node<'a> {
+  pos: usize,
+  ast: Ast<'a>,
+}
+
+impl Node<'_> {
+  // the type a node has regardless of context, `None` for parameters which can be read as either
+  fn natural_type(&self) -> Option<Type> {
+    match &self.ast {
+      Ast::Number(_) => Some(Type::Number),
+      Ast::Bool(_) => Some(Type::Boolean),
+      Ast::Ident(_) => None,
+      Ast::Str(_) => Some(Type::String),
+      Ast::Unary("!", _) => Some(Type::Boolean),
+      Ast::Unary(_, _) => Some(Type::Number),
+      Ast::Binary("+" | "-" | "*" | "/" | "%", _, _) => Some(Type::Number),
+      Ast::Binary(_, _, _) => Some(Type::Boolean),
+      Ast::Call("trigger", _) => Some(Type::Boolean),
+      Ast::Call(_, _) => Some(Type::Number),
+    }
+  }
+}
+
+struct Parser<'a> {
+  tokens: Vec<(usize, Token<'a>)>,
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn peek_op(&self, ops: &[&'static str]) -> Option<(usize, &'static str)> {
+    match self.tokens.get(self.pos) {
+      Some((pos, Token::Op(op))) if ops.contains(op) => Some((*pos, *op)),
+      _ => None,
+    }
+  }
+
+  fn expect_op(&mut self, op: &'static str) -> Result<(), ExpressionError> {
+    match self.tokens.get(self.pos) {
+      Some((_, Token::Op(o))) if *o == op => {
+        self.pos += 1;
+        Ok(())
+      }
+      Some((pos, token)) => Err(ExpressionError::UnexpectedToken(*pos, token.to_string())),
+      None => Err(ExpressionError::UnexpectedEnd),
+    }
+  }
+
+  fn binary(
+    &mut self,
+    ops: &[&'static str],
+    next: fn(&mut Self) -> Result<Node<'a>, ExpressionError>,
+  ) -> Result<Node<'a>, ExpressionError> {
+    let mut lhs = next(self)?;
+    while let Some((pos, op)) = self.peek_op(ops) {
+      self.pos += 1;
+      let rhs = next(self)?;
+      lhs = Node {
+        pos,
+        ast: Ast::Binary(op, Box::new(lhs), Box::new(rhs)),
+      };
+    }
+    Ok(lhs)
+  }
+
+  fn or(&mut self) -> Result<Node<'a>, ExpressionError> {
+    self.binary(&["||"], Self::and)
+  }
+
+  fn and(&mut self) -> Result<Node<'a>, ExpressionError> {
+    self.binary(&["&&"], Self::compare)
+  }
+
+  fn compare(&mut self) -> Result<Node<'a>, ExpressionError> {
+    let lhs = self.sum()?;
+    let Some((pos, op)) = self.peek_op(&["==", "!=", "<=", ">=", "<", ">"]) else {
+      return Ok(lhs);
+    };
+    self.pos += 1;
+    let rhs = self.sum()?;
+    Ok(Node {
+      pos,
+      ast: Ast::Binary(op, Box::new(lhs), Box::new(rhs)),
+    })
+  }
+
+  fn sum(&mut self) -> Result<Node<'a>, ExpressionError> {
+    self.binary(&["+", "-"], Self::product)
+  }
+
+  fn product(&mut self) -> Result<Node<'a>, ExpressionError> {
+    self.binary(&["*", "/", "%"], Self::unary)
+  }
+
+  fn unary(&mut self) -> Result<Node<'a>, ExpressionError> {
+    if let Some((pos, op)) = self.peek_op(&["!", "-"]) {
+      self.pos += 1;
+      let operand = self.unary()?;
+      return Ok(Node {
+        pos,
+        ast: Ast::Unary(op, Box::new(operand)),
+      });
+    }
+    self.primary()
+  }
+
+  fn primary(&mut self) -> Result<Node<'a>, ExpressionError> {
+    let Some((pos, token)) = self.tokens.get(self.pos).copied() else {
+      return Err(ExpressionError::UnexpectedEnd);
+    };
+    self.pos += 1;
+    let ast = match token {
+      Token::Number(v) => Ast::Number(
+        v.parse()
+          .map_err(|_| ExpressionError::InvalidNumber(pos, v.to_owned()))?,
+      ),
+      Token::Str(v) => Ast::Str(v),
+      Token::Ident("true") => Ast::Bool(true),
+      Token::Ident("false") => Ast::Bool(false),
+      Token::Ident(name) if self.peek_op(&["("]).is_some() => {
+        self.pos += 1;
+        let mut args = Vec::new();
+        if self.peek_op(&[")"]).is_none() {
+          args.push(self.or()?);
+          while self.peek_op(&[","]).is_some() {
+            self.pos += 1;
+            args.push(self.or()?);
+          }
+        }
+        self.expect_op(")")?;
+        Ast::Call(name, args)
+      }
+      Token::Ident(name) => Ast::Ident(name),
+      Token::Op("(") => {
+        let inner = self.or()?;
+        self.expect_op(")")?;
+        return Ok(inner);
+      }
+      Token::Op(_) => return Err(ExpressionError::UnexpectedToken(pos, token.to_string())),
+    };
+    Ok(Node { pos, ast })
+  }
+}
+
+fn boolean(node: &Node) -> Result<BoolExpr, ExpressionError> {
+  let mismatch = |found| ExpressionError::TypeMismatch(node.pos, Type::Boolean, found);
+  Ok(match &node.ast {
+    Ast::Bool(v) => BoolExpr::Const(*v),
+    Ast::Ident(name) => BoolExpr::Parameter((*name).to_owned()),
+    Ast::Unary("!", e) => BoolExpr::Not(Box::new(boolean(e)?)),
+    Ast::Binary("&&", a, b) => BoolExpr::And(Box::new(boolean(a)?), Box::new(boolean(b)?)),
+    Ast::Binary("||", a, b) => BoolExpr::Or(Box::new(boolean(a)?), Box::new(boolean(b)?)),
+    Ast::Binary(op @ ("==" | "!="), a, b)
+      if a.natural_type() == Some(Type::Boolean) || b.natural_type() == Some(Type::Boolean) =>
+    {
+      let equal = BoolExpr::Equal(Box::new(boolean(a)?), Box::new(boolean(b)?));
+      if *op == "!=" {
+        BoolExpr::Not(Box::new(equal))
+      } else {
+        equal
+      }
+    }
+    Ast::Binary(op @ ("==" | "!=" | "<" | "<=" | ">" | ">="), a, b) => {
+      let op = match *op {
+        "==" => CompareOp::Equal,
+        "!=" => CompareOp::NotEqual,
+        "<" => CompareOp::Less,
+        "<=" => CompareOp::LessOrEqual,
+        ">" => CompareOp::Greater,
+        _ => CompareOp::GreaterOrEqual,
+      };
+      BoolExpr::Compare(op, Box::new(number(a)?), Box::new(number(b)?))
+    }
+    Ast::Call("trigger", args) => match args.as_slice() {
+      [Node {
+        ast: Ast::Str(name),
+        ..
+      }] => BoolExpr::Trigger((*name).to_owned()),
+      [arg] => {
+        let found = arg.natural_type().unwrap_or(Type::Number);
+        return Err(ExpressionError::TypeMismatch(arg.pos, Type::String, found));
+      }
+      _ => {
+        return Err(ExpressionError::WrongArity(
+          node.pos,
+          "trigger".to_owned(),
+          1,
+          args.len(),
+        ))
+      }
+    },
+    _ => return Err(mismatch(node.natural_type().unwrap_or(Type::Number))),
+  })
+}
+
+fn number(node: &Node) -> Result<NumExpr, ExpressionError> {
+  let mismatch = |found| ExpressionError::TypeMismatch(node.pos, Type::Number, found);
+  Ok(match &node.ast {
+    Ast::Number(v) => NumExpr::Const(*v),
+    Ast::Ident(name) => NumExpr::Parameter((*name).to_owned()),
+    Ast::Unary("-", e) => NumExpr::Neg(Box::new(number(e)?)),
+    Ast::Binary(op @ ("+" | "-" | "*" | "/" | "%"), a, b) => {
+      let op = match *op {
+        "+" => ArithOp::Add,
+        "-" => ArithOp::Sub,
+        "*" => ArithOp::Mul,
+        "/" => ArithOp::Div,
+        _ => ArithOp::Rem,
+      };
+      NumExpr::Arith(op, Box::new(number(a)?), Box::new(number(b)?))
+    }
+    Ast::Call("trigger", _) => return Err(mismatch(Type::Boolean)),
+    Ast::Call(name, args) => {
+      let Some(function) = Function::from_name(name) else {
+        return Err(ExpressionError::UnknownFunction(
+          node.pos,
+          (*name).to_owned(),
+        ));
+      };
+      if args.len() != function.arity() {
+        return Err(ExpressionError::WrongArity(
+          node.pos,
+          (*name).to_owned(),
+          function.arity(),
+          args.len(),
+        ));
+      }
+      NumExpr::Call(function, args.iter().map(number).collect::<Result<_, _>>()?)
+    }
+    _ => return Err(mismatch(node.natural_type().unwrap_or(Type::Boolean))),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn eval(source: &str, parameters: &[(&str, f32)]) -> bool {
+    let parameters = parameters
+      .iter()
+      .map(|(name, value)| ((*name).to_owned(), *value))
+      .collect();
+    ConditionExpr::parse(source)
+      .unwrap()
+      .evaluate(&parameters, Some("jump"))
+  }
+
+  #[test]
+  fn precedence() {
+    assert!(eval("1 + 2 * 3 == 7", &[]));
+    assert!(eval("(1 + 2) * 3 == 9", &[]));
+    assert!(eval("-2 * -3 == 6 && 7 % 4 == 3", &[]));
+    assert!(eval("false && false || true", &[]));
+    assert!(!eval("false && (false || true)", &[]));
+    assert!(eval("!sprint && speed > 0.5", &[("speed", 1.0)]));
+    assert!(eval("trigger('jump') == (speed >= 1)", &[("speed", 1.0)]));
+  }
+
+  #[test]
+  fn numbers() {
+    assert!(eval("1e-3 == 0.001 && 2.5E+2 == 250 && .5 == 0.5", &[]));
+    assert_eq!(
+      ConditionExpr::parse("1.2.3 > 0").unwrap_err(),
+      ExpressionError::InvalidNumber(0, "1.2.3".to_owned())
+    );
+  }
+
+  #[test]
+  fn functions() {
+    assert!(eval(
+      "clamp(speed, 0, 1) == 1 && min(2, 3) == 2",
+      &[("speed", 4.0)]
+    ));
+    assert!(eval(
+      "abs(-2) + sign(-3) + floor(1.5) + ceil(1.5) + sqrt(4) == 6",
+      &[]
+    ));
+    assert_eq!(
+      ConditionExpr::parse("lerp(a, b) > 0").unwrap_err(),
+      ExpressionError::UnknownFunction(0, "lerp".to_owned())
+    );
+    assert_eq!(
+      ConditionExpr::parse("x > max(1)").unwrap_err(),
+      ExpressionError::WrongArity(4, "max".to_owned(), 2, 1)
+    );
+  }
+
+  #[test]
+  fn nan_does_not_panic() {
+    assert!(eval("clamp(speed, zero / zero, 1) == 1", &[("speed", 2.0)]));
+    assert!(eval(
+      "clamp(speed, 0, zero / zero) == 0",
+      &[("speed", -1.0)]
+    ));
+    assert!(!eval("zero / zero == zero / zero", &[]));
+    assert!(eval("clamp(1, 2, 0) == 2", &[]));
+  }
+
+  #[test]
+  fn errors() {
+    let error = |source| ConditionExpr::parse(source).unwrap_err();
+    assert_eq!(error("speed >"), ExpressionError::UnexpectedEnd);
+    assert_eq!(
+      error("speed > 1 )"),
+      ExpressionError::UnexpectedToken(10, "`)`".to_owned())
+    );
+    assert_eq!(error("speed # 1"), ExpressionError::UnexpectedChar(6, '#'));
+    assert_eq!(
+      error("trigger('jump"),
+      ExpressionError::UnterminatedString(8)
+    );
+    assert_eq!(
+      error("speed + 1"),
+      ExpressionError::TypeMismatch(6, Type::Boolean, Type::Number)
+    );
+    assert_eq!(
+      error("trigger(1)"),
+      ExpressionError::TypeMismatch(8, Type::String, Type::Number)
+    );
+    assert_eq!(
+      error("'run' > 1"),
+      ExpressionError::TypeMismatch(0, Type::Number, Type::String)
+    );
+  }
+}
@@ -0,0 +1,290 @@
+use bevy::prelude::*;
+use std::f32::consts::PI;
+
+use crate::{controller::entity_from_path2, lod::is_evaluated, AnimationLod, AnimatorTarget};
+
+/// Constraints applied to the animated pose of the rig every frame, after clips, blends, additive
+/// layers and property animations. Bone paths are relative to the [`AnimatorTarget`] rig.
+#[derive(Component, Default)]
+pub struct IkConstraints {
+  pub look_at: Vec<LookAtConstraint>,
+  pub two_bone: Vec<TwoBoneChain>,
+}
+
+impl IkConstraints {
+  /// Sets the world space point the named look-at constraint aims at, `None` disables it.
+  pub fn set_look_at_target(&mut self, name: &str, target: Option<Vec3>) {
+    match self.look_at.iter_mut().find(|c| c.name == name) {
+      Some(constraint) => constraint.target = target,
+      None => warn!("Look-at constraint {name:?} not found"),
+    }
+  }
+
+  /// Sets the world space point the end of the named chain reaches for, `None` disables it.
+  pub fn set_chain_target(&mut self, name: &str, target: Option<Vec3>) {
+    match self.two_bone.iter_mut().find(|c| c.name == name) {
+      Some(chain) => chain.target = target,
+      None => warn!("IK chain {name:?} not found"),
+    }
+  }
+}
+
+/// Turns a bone towards a point, e.g. a head towards something interesting.
+pub struct LookAtConstraint {
+  pub name: String,
+  pub bone: EntityPath,
+  /// Bone local axis that should point at the target
+  pub forward: Vec3,
+  /// How far the bone may turn away from its animated direction, in radians
+  pub max_angle: f32,
+  pub weight: f32,
+  pub target: Option<Vec3>,
+  path_cache: Vec<Option<Entity>>,
+}
+
+impl LookAtConstraint {
+  pub fn new(name: impl Into<String>, bone: EntityPath) -> Self {
+    Self {
+      name: name.into(),
+      bone,
+      forward: Vec3::Z,
+      max_angle: PI,
+      weight: 1.0,
+      target: None,
+      path_cache: Vec::new(),
+    }
+  }
+}
+
+/// Bends a thigh/shin/foot (or upper arm/forearm/hand) chain so the end reaches the target.
+pub struct TwoBoneChain {
+  pub name: String,
+  pub upper: EntityPath,
+  pub lower: EntityPath,
+  pub end: EntityPath,
+  /// World space point the middle joint bends towards, the animated bend direction if `None`
+  pub pole: Option<Vec3>,
+  /// Range the middle joint may bend in, in radians away from a straight chain
+  pub min_bend: f32,
+  pub max_bend: f32,
+  pub weight: f32,
+  pub target: Option<Vec3>,
+  path_caches: [Vec<Option<Entity>>; 3],
+}
+
+impl TwoBoneChain {
+  pub fn new(
+    name: impl Into<String>,
+    upper: EntityPath,
+    lower: EntityPath,
+    end: EntityPath,
+  ) -> Self {
+    Self {
+      name: name.into(),
+      upper,
+      lower,
+      end,
+      pole: None,
+      min_bend: 0.0,
+      max_bend: PI,
+      weight: 1.0,
+      target: None,
+      path_caches: Default::default(),
+    }
+  }
+}
+
+/// World space rotation that turns `forward`, pointing out of a bone at `position`, towards
+/// `target` by at most `max_angle`.
+pub fn solve_look_at(position: Vec3, forward: Vec3, target: Vec3, max_angle: f32) -> Quat {
+  let (Some(from), Some(to)) = (forward.try_normalize(), (target - position).try_normalize())
+  else {
+    return Quat::IDENTITY;
+  };
+  let angle = from.angle_between(to);
+  if angle <= f32::EPSILON {
+    return Quat::IDENTITY;
+  }
+  let axis = from
+    .cross(to)
+    .try_normalize()
+    .unwrap_or_else(|| from.any_orthonormal_vector());
+  Quat::from_axis_angle(axis, angle.min(max_angle.max(0.0)))
+}
+
+/// Solves a two-bone chain with joints at `joints` (upper, middle, end) in world space so the end
+/// reaches `target`, or points at it fully stretched when out of reach.
+///
+/// Returns the world space rotations to apply to the upper bone and, before that, to the lower
+/// bone, i.e. the lower bone ends up rotated by `upper * lower`.
+pub fn solve_two_bone(
+  joints: [Vec3; 3],
+  target: Vec3,
+  pole: Option<Vec3>,
+  min_bend: f32,
+  max_bend: f32,
+) -> (Quat, Quat) {
+  let [a, b, c] = joints;
+  let (lab, lcb) = (a.distance(b), b.distance(c));
+  let (Some(ac_dir), Some(at_dir)) = ((c - a).try_normalize(), (target - a).try_normalize()) else {
+    return (Quat::IDENTITY, Quat::IDENTITY);
+  };
+  if lab <= f32::EPSILON || lcb <= f32::EPSILON {
+    return (Quat::IDENTITY, Quat::IDENTITY);
+  }
+
+  // current interior angles at the upper and middle joints
+  let ac_ab_0 = (c - a).angle_between(b - a);
+  let ba_bc_0 = (a - b).angle_between(c - b);
+
+  // interior angles of the triangle reaching the target, within the bend limits
+  let lat = a.distance(target).min(lab + lcb);
+  let cos_knee = (lab * lab + lcb * lcb - lat * lat) / (2.0 * lab * lcb);
+  let bend = (PI - cos_knee.clamp(-1.0, 1.0).acos()).clamp(min_bend.max(0.0), max_bend.min(PI));
+  let ba_bc_1 = PI - bend;
+  let reach = (lab * lab + lcb * lcb - 2.0 * lab * lcb * ba_bc_1.cos())
+    .max(0.0)
+    .sqrt();
+  let ac_ab_1 = if reach <= f32::EPSILON {
+    0.0
+  } else {
+    ((lab * lab + reach * reach - lcb * lcb) / (2.0 * lab * reach))
+      .clamp(-1.0, 1.0)
+      .acos()
+  };
+
+  // bend in the plane of the chain, or towards the pole when the chain is straight
+  let axis = (c - a)
+    .cross(b - a)
+    .try_normalize()
+    .or_else(|| pole.and_then(|p| (c - a).cross(p - a).try_normalize()))
+    .unwrap_or_else(|| ac_dir.any_orthonormal_vector());
+  let upper_bend = Quat::from_axis_angle(axis, ac_ab_1 - ac_ab_0);
+  let lower = Quat::from_axis_angle(axis, ba_bc_1 - ba_bc_0);
+
+  // aim the reshaped chain at the target, then twist the middle joint towards the pole
+  let mut upper = Quat::from_rotation_arc(ac_dir, at_dir) * upper_bend;
+  if let Some(pole) = pole {
+    let project = |v: Vec3| v - at_dir * v.dot(at_dir);
+    let knee = project(upper * (b - a));
+    let pole = project(pole - a);
+    if knee.length_squared() > f32::EPSILON && pole.length_squared() > f32::EPSILON {
+      let twist = at_dir.dot(knee.cross(pole)).atan2(knee.dot(pole));
+      upper = Quat::from_axis_angle(at_dir, twist) * upper;
+    }
+  }
+  (upper, lower)
+}
+
+pub(crate) fn apply_ik(
+  mut qry: Query<(&AnimatorTarget, &mut IkConstraints, Option<&AnimationLod>)>,
+  mut transforms: Query<&mut Transform>,
+  parents: Query<&Parent>,
+  children: Query<&Children>,
+  names: Query<&Name>,
+) {
+  for (target, mut ik, lod) in qry.iter_mut() {
+    let Some(rig) = target.rig_target else {
+      continue;
+    };
+    if !is_evaluated(lod) {
+      continue;
+    }
+    let ik = &mut *ik;
+
+    for chain in ik.two_bone.iter_mut() {
+      let Some(target) = chain.target else {
+        continue;
+      };
+      if chain.weight <= 0.0 {
+        continue;
+      }
+      let [upper_cache, lower_cache, end_cache] = &mut chain.path_caches;
+      let (Some(upper), Some(lower), Some(end)) = (
+        entity_from_path2(rig, &chain.upper, &children, &names, upper_cache),
+        entity_from_path2(rig, &chain.lower, &children, &names, lower_cache),
+        entity_from_path2(rig, &chain.end, &children, &names, end_cache),
+      ) else {
+        continue;
+      };
+      let joints =
+        [upper, lower, end].map(|e| world_transform(e, &transforms, &parents).translation);
+      let (upper_rotation, lower_rotation) =
+        solve_two_bone(joints, target, chain.pole, chain.min_bend, chain.max_bend);
+      // the lower bone first, while its parent still has the animated rotation
+      let weight = chain.weight.min(1.0);
+      rotate_world(
+        lower,
+        Quat::IDENTITY.slerp(lower_rotation, weight),
+        &mut transforms,
+        &parents,
+      );
+      rotate_world(
+        upper,
+        Quat::IDENTITY.slerp(upper_rotation, weight),
+        &mut transforms,
+        &parents,
+      );
+    }
+
+    // after the chains so a spine or head follows the body
+    for look_at in ik.look_at.iter_mut() {
+      let Some(target) = look_at.target else {
+        continue;
+      };
+      if look_at.weight <= 0.0 {
+        continue;
+      }
+      let Some(bone) = entity_from_path2(
+        rig,
+        &look_at.bone,
+        &children,
+        &names,
+        &mut look_at.path_cache,
+      ) else {
+        continue;
+      };
+      let world = world_transform(bone, &transforms, &parents);
+      let rotation = solve_look_at(
+        world.translation,
+        world.rotation * look_at.forward,
+        target,
+        look_at.max_angle,
+      );
+      let rotation = Quat::IDENTITY.slerp(rotation, look_at.weight.min(1.0));
+      rotate_world(bone, rotation, &mut transforms, &parents);
+    }
+  }
+}
+
+/// The transform `TransformPropagate` is going to compute, from the local transforms of this frame.
+fn world_transform(
+  entity: Entity,
+  transforms: &Query<&mut Transform>,
+  parents: &Query<&Parent>,
+) -> Transform {
+  let mut world = transforms.get(entity).copied().unwrap_or_default();
+  let mut current = entity;
+  while let Ok(parent) = parents.get(current) {
+    current = parent.get();
+    if let Ok(transform) = transforms.get(current) {
+      world = transform.mul_transform(world);
+    }
+  }
+  world
+}
+
+fn rotate_world(
+  entity: Entity,
+  rotation: Quat,
+  transforms: &mut Query<&mut Transform>,
+  parents: &Query<&Parent>,
+) {
+  let parent_rotation = parents.get(entity).map_or(Quat::IDENTITY, |p| {
+    world_transform(p.get(), transforms, parents).rotation
+  });
+  if let Ok(mut transform) = transforms.get_mut(entity) {
+    transform.rotation =
+      (parent_rotation.inverse() * rotation * parent_rotation * transform.rotation).normalize();
+  }
+}
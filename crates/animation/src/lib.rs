@@ -1,5 +1,5 @@
 use assets::RonAssetApp;
-use bevy::prelude::*;
+use bevy::{animation::animation_player, prelude::*, transform::TransformSystem};
 
 #[derive(Default)]
 pub struct AnimationControllerPlugin;
@@ -8,28 +8,105 @@ impl Plugin for AnimationControllerPlugin {
   fn build(&self, app: &mut App) {
     app
       .register_ron_asset::<BasicAnimationController>()
+      .register_ron_asset::<PropertyAnimation>()
+      .register_ron_asset::<RetargetMap>()
       .register_type::<Animator<BasicAnimationController>>()
       .register_type::<AnimatorTarget>()
+      .register_type::<AnimatorOf>()
       .register_type::<AnimationControllerInput>()
       .register_type::<AnimationControllerData<BasicAnimationController>>()
       .add_systems(
         Update,
         ((
+          register_animators,
           find_rig_target::<BasicAnimationController>,
+          share_parameters,
+          restore_animation_snapshots::<BasicAnimationController>,
+          reload_controllers::<BasicAnimationController>,
           play_animations::<BasicAnimationController>,
         )
           .chain(),),
+      )
+      .add_systems(
+        PostUpdate,
+        (
+          update_animation_lod.before(capture_blend_sources),
+          capture_blend_sources.before(animation_player),
+          resume_animation_lod
+            .after(animation_player)
+            .before(apply_blends),
+          apply_blends
+            .after(animation_player)
+            .before(TransformSystem::TransformPropagate),
+          apply_additive_layers::<BasicAnimationController>
+            .after(apply_blends)
+            .before(TransformSystem::TransformPropagate),
+          animate_properties
+            .after(apply_additive_layers::<BasicAnimationController>)
+            .before(TransformSystem::TransformPropagate),
+          apply_ik
+            .after(animate_properties)
+            .before(TransformSystem::TransformPropagate),
+          capture_animation_snapshots::<BasicAnimationController>.after(apply_blends),
+        ),
       );
+    // the editor saves documents where the default asset source reads them from
+    #[cfg(feature = "editor")]
+    if let Some(root) = app
+      .get_added_plugins::<AssetPlugin>()
+      .first()
+      .map(|plugin| editor::asset_root(plugin))
+    {
+      app.insert_resource(AnimationGraphEditor::new(root));
+    }
   }
 }
 
+mod additive;
 mod animator;
 mod basic_controller;
+mod blend;
+mod clips;
 mod controller;
+#[cfg(feature = "editor")]
+mod editor;
+mod expression;
+mod ik;
+mod lint;
+mod lod;
+mod mirror;
+mod property;
+mod retarget;
+mod snapshot;
 
+use additive::apply_additive_layers;
+pub use additive::{
+  AdditiveCommandsExt, AdditiveLayer, AdditiveLayers, AdditiveWeight, PlayAdditive,
+};
+use animator::{register_animators, share_parameters};
 pub use animator::{
-  AnimatedBundle, AnimationControllerData, AnimationControllerInput, Animator, AnimatorTarget,
+  AnimatedBundle, AnimationControllerData, AnimationControllerInput, Animator, AnimatorOf,
+  AnimatorTarget, Animators,
+};
+pub use basic_controller::{BasicAnimationController, BasicAnimationControllerData, BasicNodeId};
+use blend::{apply_blends, capture_blend_sources};
+pub use blend::{AnimationBlend, Blend, BlendCurve, BlendMode};
+use controller::{find_rig_target, play_animations, reload_controllers};
+pub use controller::{AnimationController, AnimationOutput};
+#[cfg(feature = "editor")]
+pub use editor::{animation_graph_editor, AnimationGraphEditor};
+pub use expression::{ConditionExpr, ExpressionError};
+use ik::apply_ik;
+pub use ik::{solve_look_at, solve_two_bone, IkConstraints, LookAtConstraint, TwoBoneChain};
+use lod::{resume_animation_lod, update_animation_lod};
+pub use lod::{AnimationLod, AnimationLodLevel};
+pub use mirror::{BonePairing, Mirror, MirrorAxis};
+use property::animate_properties;
+pub use property::{
+  Interpolation, PropertyAnimation, PropertyPlayer, PropertyTarget, PropertyTrack, PropertyValue,
+};
+pub use retarget::RetargetMap;
+use snapshot::{capture_animation_snapshots, restore_animation_snapshots};
+pub use snapshot::{
+  AnimationSnapshot, ClipSnapshot, RestoreAnimation, TransitionSnapshot, TransitionSourceSnapshot,
 };
-pub use basic_controller::BasicAnimationController;
-pub use controller::AnimationController;
-use controller::{find_rig_target, play_animations};
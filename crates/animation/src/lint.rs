@@ -0,0 +1,117 @@
+use bevy::{gltf::Gltf, prelude::*};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+  basic_controller::{
+    BasicAnimationControllerData, BasicAnimationTransitionCondition, BasicNodeId,
+  },
+  BasicAnimationController,
+};
+
+/// Inspection of a loaded controller for tooling, e.g. checking it without running the game.
+impl BasicAnimationController {
+  /// The glTF file node clips are looked up in by name.
+  pub fn model(&self) -> Option<&Handle<Gltf>> {
+    self.assets.as_ref().and_then(|a| a.model.as_ref())
+  }
+
+  /// Clip played by each node that has one.
+  pub fn node_clips(&self) -> impl Iterator<Item = (&BasicNodeId, &Handle<AnimationClip>)> {
+    self.assets.iter().flat_map(|a| a.animations.iter())
+  }
+
+  /// Names of the parameters read by transition conditions.
+  pub fn parameters(&self) -> BTreeSet<&str> {
+    let mut parameters = BTreeSet::new();
+    for condition in self.edges.iter().flat_map(|e| e.conditions.iter()) {
+      match condition {
+        BasicAnimationTransitionCondition::GreaterThan(name, _)
+        | BasicAnimationTransitionCondition::LessThan(name, _) => {
+          parameters.insert(name.as_str());
+        }
+        BasicAnimationTransitionCondition::Trigger(_) => {}
+        BasicAnimationTransitionCondition::Expression(expr) => parameters.extend(expr.parameters()),
+      }
+    }
+    parameters
+  }
+
+  /// Names of the triggers checked by transition conditions.
+  pub fn triggers(&self) -> BTreeSet<&str> {
+    let mut triggers = BTreeSet::new();
+    for condition in self.edges.iter().flat_map(|e| e.conditions.iter()) {
+      match condition {
+        BasicAnimationTransitionCondition::Trigger(name) => {
+          triggers.insert(name.as_str());
+        }
+        BasicAnimationTransitionCondition::Expression(expr) => triggers.extend(expr.triggers()),
+        _ => {}
+      }
+    }
+    triggers
+  }
+
+  /// Problems with the graph itself that loading does not reject.
+  pub fn lint(&self) -> Vec<String> {
+    let mut problems = Vec::new();
+    match &self.default_node {
+      Some(id) if !self.nodes.contains_key(id) => {
+        problems.push(format!("default node {:?} does not exist", id.as_str()))
+      }
+      Some(_) => {}
+      None => problems.push("no default node".to_owned()),
+    }
+    for edge in self.edges.iter() {
+      for id in edge.from.iter().chain([&edge.to]) {
+        if !self.nodes.contains_key(id) {
+          problems.push(format!(
+            "transition {} refers to missing node {:?}",
+            describe(&edge.from, &edge.to),
+            id.as_str()
+          ));
+        }
+      }
+      if edge.enabled && edge.conditions.is_empty() && edge.from.is_none() {
+        problems.push(format!(
+          "transition {} has no conditions and is taken from every node",
+          describe(&edge.from, &edge.to)
+        ));
+      }
+    }
+    let mut unreachable: Vec<_> = self
+      .nodes
+      .keys()
+      .filter(|id| {
+        self.default_node.as_ref() != Some(*id)
+          && !self.edges.iter().any(|e| e.enabled && &e.to == *id)
+      })
+      .map(|id| id.as_str())
+      .collect();
+    unreachable.sort();
+    for id in unreachable {
+      problems.push(format!("node {id:?} is never entered"));
+    }
+    problems
+  }
+
+  /// Evaluates the transitions once like the controller does when its parameters change, without
+  /// playing anything. Returns the node entered, if any.
+  pub fn simulate(
+    &self,
+    parameters: &HashMap<String, f32>,
+    trigger: Option<&str>,
+    data: &mut BasicAnimationControllerData,
+  ) -> Option<BasicNodeId> {
+    let transition = self.get_transition(parameters, trigger, data)?;
+    if !self.nodes.contains_key(&transition.to) {
+      return None;
+    }
+    data.active_node = Some(transition.to.clone());
+    Some(transition.to.clone())
+  }
+}
+
+fn describe(from: &Option<BasicNodeId>, to: &BasicNodeId) -> String {
+  let from = from.as_ref().map_or("*", |id| id.as_str());
+  format!("{from} -> {}", to.as_str())
+}
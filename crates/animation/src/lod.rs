@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+
+use crate::{AnimatorOf, AnimatorTarget};
+
+/// Lowers how often the pose of an animated entity is evaluated when it is far from the camera or
+/// not visible. The controller keeps running and clips keep their time, so the pose is correct
+/// again as soon as it is evaluated.
+#[derive(Component)]
+pub struct AnimationLod {
+  /// Camera distance from which the pose is evaluated every other frame
+  pub half_distance: f32,
+  /// Camera distance from which the pose is evaluated every fourth frame
+  pub quarter_distance: f32,
+  /// Camera distance from which the pose is not evaluated at all
+  pub frozen_distance: f32,
+  /// Stop evaluating the pose while none of the meshes of the entity are visible
+  pub freeze_hidden: bool,
+  level: AnimationLodLevel,
+  frame: u32,
+  evaluate: bool,
+  /// The player is paused while the pose is not evaluated this frame
+  paused: bool,
+  /// Clip that was skipped and for how long, caught up with when it is evaluated again
+  skipped_clip: Option<Handle<AnimationClip>>,
+  skipped: f32,
+  meshes: Vec<Entity>,
+}
+
+impl Default for AnimationLod {
+  fn default() -> Self {
+    Self {
+      half_distance: 15.0,
+      quarter_distance: 30.0,
+      frozen_distance: f32::INFINITY,
+      freeze_hidden: true,
+      level: AnimationLodLevel::Full,
+      frame: 0,
+      evaluate: true,
+      paused: false,
+      skipped_clip: None,
+      skipped: 0.0,
+      meshes: Vec::new(),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnimationLodLevel {
+  #[default]
+  Full,
+  Half,
+  Quarter,
+  Frozen,
+}
+
+impl AnimationLod {
+  pub fn level(&self) -> AnimationLodLevel {
+    self.level
+  }
+
+  /// Whether the pose is evaluated this frame.
+  pub fn is_evaluated(&self) -> bool {
+    self.evaluate
+  }
+}
+
+/// Whether the pose of an entity with an optional [`AnimationLod`] is evaluated this frame.
+pub(crate) fn is_evaluated(lod: Option<&AnimationLod>) -> bool {
+  match lod {
+    Some(lod) => lod.evaluate,
+    None => true,
+  }
+}
+
+pub(crate) fn update_animation_lod(
+  time: Res<Time>,
+  cameras: Query<(&Camera, &GlobalTransform)>,
+  mut qry: Query<(
+    Entity,
+    &AnimatorTarget,
+    &mut AnimationLod,
+    Option<&AnimatorOf>,
+  )>,
+  transforms: Query<&GlobalTransform>,
+  mut players: Query<&mut AnimationPlayer>,
+  children: Query<&Children>,
+  visibility: Query<&ViewVisibility, With<Handle<Mesh>>>,
+) {
+  let delta = time.delta_seconds();
+  for (entity, target, mut lod, animator_of) in qry.iter_mut() {
+    let lod = &mut *lod;
+    let Some(rig) = target.rig_target else {
+      continue;
+    };
+    let root = animator_of.map_or(entity, |a| a.owner);
+    let Ok(transform) = transforms.get(root) else {
+      continue;
+    };
+    let Ok(mut player) = players.get_mut(rig) else {
+      continue;
+    };
+
+    if lod.meshes.is_empty() {
+      lod.meshes = children
+        .iter_descendants(root)
+        .filter(|e| visibility.contains(*e))
+        .collect();
+    }
+    let visible = lod.meshes.is_empty()
+      || lod
+        .meshes
+        .iter()
+        .any(|mesh| visibility.get(*mesh).is_ok_and(|v| v.get()));
+    let distance = cameras
+      .iter()
+      .filter(|(camera, _)| camera.is_active)
+      .map(|(_, camera)| camera.translation().distance(transform.translation()))
+      .reduce(f32::min)
+      .unwrap_or(0.0);
+
+    lod.level = if (lod.freeze_hidden && !visible) || distance >= lod.frozen_distance {
+      AnimationLodLevel::Frozen
+    } else if distance >= lod.quarter_distance {
+      AnimationLodLevel::Quarter
+    } else if distance >= lod.half_distance {
+      AnimationLodLevel::Half
+    } else {
+      AnimationLodLevel::Full
+    };
+    let interval = match lod.level {
+      AnimationLodLevel::Full => 1,
+      AnimationLodLevel::Half => 2,
+      AnimationLodLevel::Quarter => 4,
+      AnimationLodLevel::Frozen => 0,
+    };
+    lod.frame = lod.frame.wrapping_add(1);
+    // spread updates of a crowd over frames
+    lod.evaluate = interval != 0 && lod.frame.wrapping_add(entity.index()) % interval == 0;
+
+    if lod.evaluate {
+      // catch the clip up with the time it missed
+      if let Some(clip) = lod.skipped_clip.take() {
+        if player.animation_clip() == &clip {
+          let seek_time = player.seek_time() + lod.skipped * player.speed();
+          player.seek_to(seek_time);
+        }
+      }
+      lod.skipped = 0.0;
+    } else if !player.is_paused() {
+      if lod.skipped_clip.as_ref() != Some(player.animation_clip()) {
+        // the controller switched clips, which start from the beginning
+        lod.skipped_clip = Some(player.animation_clip().clone());
+        lod.skipped = 0.0;
+      }
+      lod.skipped += delta;
+      // only for the animation player, players paused by someone else are left alone
+      player.pause();
+      lod.paused = true;
+    }
+  }
+}
+
+/// Resumes the players [`update_animation_lod`] paused, so the pause is never seen outside of the
+/// animation player.
+pub(crate) fn resume_animation_lod(
+  mut qry: Query<(&AnimatorTarget, &mut AnimationLod)>,
+  mut players: Query<&mut AnimationPlayer>,
+) {
+  for (target, mut lod) in qry.iter_mut() {
+    if !lod.paused {
+      continue;
+    }
+    lod.paused = false;
+    if let Some(mut player) = target.rig_target.and_then(|rig| players.get_mut(rig).ok()) {
+      player.resume();
+    }
+  }
+}
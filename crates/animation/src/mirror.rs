@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::clips::clip_curves;
+
+/// How clips of nodes with `mirror: true` are mirrored. Assumes the local axes of paired bones
+/// mirror each other, as they do in rigs built with symmetry.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Mirror {
+  /// Axis of the rig that is flipped
+  #[serde(default)]
+  pub axis: MirrorAxis,
+  /// How the bone on the other side of a bone is named
+  #[serde(default = "default_pairs")]
+  pub pairs: Vec<BonePairing>,
+}
+
+impl Default for Mirror {
+  fn default() -> Self {
+    Self {
+      axis: MirrorAxis::default(),
+      pairs: default_pairs(),
+    }
+  }
+}
+
+fn default_pairs() -> Vec<BonePairing> {
+  vec![BonePairing::Suffix(".L".to_owned(), ".R".to_owned())]
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default)]
+pub enum MirrorAxis {
+  #[default]
+  X,
+  Y,
+  Z,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum BonePairing {
+  /// e.g. `Suffix(".L", ".R")` pairs `hand.L` and `hand.R`
+  Suffix(String, String),
+  /// e.g. `Prefix("Left", "Right")` pairs `LeftHand` and `RightHand`
+  Prefix(String, String),
+}
+
+impl BonePairing {
+  fn opposite(&self, name: &str) -> Option<String> {
+    match self {
+      BonePairing::Suffix(a, b) => {
+        if let Some(base) = name.strip_suffix(a.as_str()) {
+          Some(format!("{base}{b}"))
+        } else {
+          name
+            .strip_suffix(b.as_str())
+            .map(|base| format!("{base}{a}"))
+        }
+      }
+      BonePairing::Prefix(a, b) => {
+        if let Some(base) = name.strip_prefix(a.as_str()) {
+          Some(format!("{b}{base}"))
+        } else {
+          name
+            .strip_prefix(b.as_str())
+            .map(|base| format!("{a}{base}"))
+        }
+      }
+    }
+  }
+}
+
+impl Mirror {
+  /// Name of the bone on the other side of `name`, `name` itself for bones in the middle.
+  pub fn opposite(&self, name: &Name) -> Name {
+    self
+      .pairs
+      .iter()
+      .find_map(|pairing| pairing.opposite(name.as_str()))
+      .map_or_else(|| name.clone(), Name::new)
+  }
+
+  /// Copy of `clip` with the animation of every bone moved to its opposite bone and reflected.
+  pub fn mirror(&self, clip: &AnimationClip) -> AnimationClip {
+    let normal = match self.axis {
+      MirrorAxis::X => Vec3::X,
+      MirrorAxis::Y => Vec3::Y,
+      MirrorAxis::Z => Vec3::Z,
+    };
+    let mut mirrored = AnimationClip::default();
+    for (path, curves) in clip_curves(clip) {
+      let path = EntityPath {
+        parts: path.parts.iter().map(|part| self.opposite(part)).collect(),
+      };
+      for curve in curves {
+        let keyframes = match &curve.keyframes {
+          Keyframes::Translation(keys) => Keyframes::Translation(
+            keys
+              .iter()
+              .map(|t| *t - 2.0 * t.dot(normal) * normal)
+              .collect(),
+          ),
+          Keyframes::Rotation(keys) => Keyframes::Rotation(
+            keys
+              .iter()
+              .map(|r| {
+                // the axis of a reflected rotation is reflected and reversed
+                let axis = r.xyz();
+                let axis = 2.0 * axis.dot(normal) * normal - axis;
+                Quat::from_xyzw(axis.x, axis.y, axis.z, r.w)
+              })
+              .collect(),
+          ),
+          keyframes => keyframes.clone(),
+        };
+        mirrored.add_curve_to_path(
+          path.clone(),
+          VariableCurve {
+            keyframe_timestamps: curve.keyframe_timestamps.clone(),
+            keyframes,
+          },
+        );
+      }
+    }
+    mirrored
+  }
+}
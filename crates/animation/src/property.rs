@@ -0,0 +1,578 @@
+use assets::{RonAsset, RonAssetLoaderError};
+use bevy::{
+  asset::{LoadContext, ReflectAsset, ReflectHandle, UntypedHandle},
+  ecs::reflect::AppTypeRegistry,
+  prelude::*,
+  reflect::{GetPath, TypeRegistration, TypeRegistry},
+  utils::{BoxedFuture, HashSet},
+};
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
+
+use crate::{AnimatorOf, BlendCurve};
+
+/// Keyframed values written to reflected fields of components or assets. Nodes of an animation
+/// controller can play one alongside (or instead of) their clip.
+#[derive(Deserialize, Serialize, Clone, Asset, TypePath)]
+pub struct PropertyAnimation {
+  tracks: Vec<PropertyTrack>,
+  #[serde(skip)]
+  duration: f32,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct PropertyTrack {
+  /// Names leading from the animated entity to the target, empty for the animated entity itself
+  #[serde(default)]
+  pub path: Vec<String>,
+  /// Also animate every descendant of the target that has the property
+  #[serde(default)]
+  pub descendants: bool,
+  pub property: PropertyTarget,
+  #[serde(default)]
+  pub interpolation: Interpolation,
+  pub keyframes: Vec<(f32, PropertyValue)>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub enum PropertyTarget {
+  /// Component type name and reflect path of the field, e.g. `Component("Transform", "scale")`
+  Component(String, String),
+  /// Asset type name and field path, e.g. `Asset("StandardMaterial", "emissive")`. The asset is
+  /// found through the entity's `Handle` to it, which is pointed at a copy while the animation
+  /// plays so other users of the asset are left alone
+  Asset(String, String),
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
+pub enum PropertyValue {
+  Float(f32),
+  Vec2(f32, f32),
+  Vec3(f32, f32, f32),
+  Color(f32, f32, f32, f32),
+}
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+pub enum Interpolation {
+  Step,
+  #[default]
+  Linear,
+  Curve(BlendCurve),
+}
+
+impl PropertyValue {
+  fn lerp(&self, to: &Self, t: f32) -> Self {
+    let l = |a: f32, b: f32| a + (b - a) * t;
+    match (*self, *to) {
+      (Self::Float(a), Self::Float(b)) => Self::Float(l(a, b)),
+      (Self::Vec2(a0, a1), Self::Vec2(b0, b1)) => Self::Vec2(l(a0, b0), l(a1, b1)),
+      (Self::Vec3(a0, a1, a2), Self::Vec3(b0, b1, b2)) => {
+        Self::Vec3(l(a0, b0), l(a1, b1), l(a2, b2))
+      }
+      (Self::Color(a0, a1, a2, a3), Self::Color(b0, b1, b2, b3)) => {
+        Self::Color(l(a0, b0), l(a1, b1), l(a2, b2), l(a3, b3))
+      }
+      _ => *self,
+    }
+  }
+
+  fn write(&self, field: &mut dyn Reflect) -> bool {
+    match *self {
+      Self::Float(v) => field.downcast_mut::<f32>().map(|f| *f = v).is_some(),
+      Self::Vec2(x, y) => field
+        .downcast_mut::<Vec2>()
+        .map(|f| *f = Vec2::new(x, y))
+        .is_some(),
+      Self::Vec3(x, y, z) => field
+        .downcast_mut::<Vec3>()
+        .map(|f| *f = Vec3::new(x, y, z))
+        .is_some(),
+      Self::Color(r, g, b, a) => field
+        .downcast_mut::<Color>()
+        .map(|f| *f = Color::rgba(r, g, b, a))
+        .is_some(),
+    }
+  }
+}
+
+impl PropertyTrack {
+  fn sample(&self, time: f32) -> Option<PropertyValue> {
+    let next = self.keyframes.iter().position(|(t, _)| *t > time);
+    match next {
+      None => self.keyframes.last().map(|(_, v)| *v),
+      Some(0) => self.keyframes.first().map(|(_, v)| *v),
+      Some(next) => {
+        let (t0, from) = &self.keyframes[next - 1];
+        let (t1, to) = &self.keyframes[next];
+        let progress = (time - t0) / (t1 - t0);
+        Some(match &self.interpolation {
+          Interpolation::Step => *from,
+          Interpolation::Linear => from.lerp(to, progress),
+          Interpolation::Curve(curve) => from.lerp(to, curve.sample(progress)),
+        })
+      }
+    }
+  }
+}
+
+impl PropertyAnimation {
+  pub fn duration(&self) -> f32 {
+    self.duration
+  }
+}
+
+impl RonAsset for PropertyAnimation {
+  type NestedAssets = ();
+  type Settings = ();
+  fn construct_nested_assets<'a>(
+    &'a mut self,
+    _load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), RonAssetLoaderError>> {
+    Box::pin(async move {
+      for track in self.tracks.iter_mut() {
+        track.keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut values = track
+          .keyframes
+          .iter()
+          .map(|(_, v)| std::mem::discriminant(v));
+        if let Some(first) = values.next() {
+          if values.any(|v| v != first) {
+            return Err(RonAssetLoaderError::Invalid(
+              "property track keyframes must all have the same value type".to_owned(),
+            ));
+          }
+        }
+        if let Some((time, _)) = track.keyframes.last() {
+          self.duration = self.duration.max(*time);
+        }
+      }
+      Ok(())
+    })
+  }
+  fn extensions() -> &'static [&'static str] {
+    &["prop.anim.ron"]
+  }
+}
+
+/// Playback state of the [`PropertyAnimation`] on an animated entity.
+#[derive(Component, Default)]
+pub struct PropertyPlayer {
+  active: Option<PlayingProperties>,
+  /// Tracks and target entities of the playing animation that could not be written, warned about
+  /// once instead of every frame
+  failed: HashSet<(usize, Entity)>,
+  /// What the playing animation changed, restored when it stops
+  originals: Vec<Original>,
+  /// Originals of stopped animations, restored when properties are next animated
+  restore: Vec<Original>,
+}
+
+/// A value as it was before an animation first wrote to it.
+enum Original {
+  /// Field of a component on the entity
+  Field {
+    entity: Entity,
+    component: TypeId,
+    field: String,
+    value: Box<dyn Reflect>,
+  },
+  /// Handle of the entity to an asset, replaced with a `copy` the animation writes to
+  Asset {
+    entity: Entity,
+    handle_type: TypeId,
+    handle: UntypedHandle,
+    copy: UntypedHandle,
+  },
+}
+
+struct PlayingProperties {
+  animation: Handle<PropertyAnimation>,
+  time: f32,
+  speed: f32,
+  repeat: bool,
+}
+
+impl PropertyPlayer {
+  pub fn play(&mut self, animation: Handle<PropertyAnimation>, speed: f32, repeat: bool) {
+    if let Some(active) = &mut self.active {
+      if active.animation == animation {
+        active.speed = speed;
+        active.repeat = repeat;
+        return;
+      }
+    }
+    self.active = Some(PlayingProperties {
+      animation,
+      time: 0.0,
+      speed,
+      repeat,
+    });
+    self.failed.clear();
+    self.restore.append(&mut self.originals);
+  }
+
+  pub fn stop(&mut self) {
+    self.active = None;
+    self.failed.clear();
+    self.restore.append(&mut self.originals);
+  }
+
+  pub fn animation(&self) -> Option<&Handle<PropertyAnimation>> {
+    self.active.as_ref().map(|a| &a.animation)
+  }
+}
+
+pub(crate) fn animate_properties(world: &mut World) {
+  let delta = world.resource::<Time>().delta_seconds();
+  let registry = world.resource::<AppTypeRegistry>().clone();
+  let registry = registry.read();
+
+  world.resource_scope(|world, animations: Mut<Assets<PropertyAnimation>>| {
+    let mut playing = Vec::new();
+    let mut restore = Vec::new();
+    let mut players = world.query::<(Entity, &mut PropertyPlayer)>();
+    for (entity, mut player) in players.iter_mut(world) {
+      restore.append(&mut player.restore);
+      let Some(active) = &mut player.active else {
+        continue;
+      };
+      let Some(animation) = animations.get(&active.animation) else {
+        continue;
+      };
+      active.time += delta * active.speed;
+      if active.repeat && animation.duration > 0.0 {
+        active.time = active.time.rem_euclid(animation.duration);
+      }
+      let (id, time) = (active.animation.id(), active.time);
+      let originals = std::mem::take(&mut player.originals);
+      playing.push((entity, entity, id, time, originals));
+    }
+    for original in restore {
+      restore_original(world, &registry, original);
+    }
+    // tracks of one of several animators start at the entity they animate
+    for (_, entity, ..) in playing.iter_mut() {
+      if let Some(animator_of) = world.get::<AnimatorOf>(*entity) {
+        *entity = animator_of.owner;
+      }
+    }
+
+    let mut targets = Vec::new();
+    let mut failed = Vec::new();
+    let mut originals = Vec::new();
+    for (player, entity, id, time, mut recorded) in playing {
+      let Some(animation) = animations.get(id) else {
+        originals.push((player, recorded));
+        continue;
+      };
+      for (index, track) in animation.tracks.iter().enumerate() {
+        let Some(value) = track.sample(time) else {
+          continue;
+        };
+        targets.clear();
+        let Some(target) = find_path(world, entity, &track.path) else {
+          continue;
+        };
+        targets.push(target);
+        if track.descendants {
+          collect_descendants(world, target, &mut targets);
+        }
+        for target in targets.iter() {
+          let written = write_property(
+            world,
+            &registry,
+            *target,
+            &track.property,
+            &value,
+            !track.descendants,
+            &mut recorded,
+          );
+          if let Err(error) = written {
+            failed.push((player, index, *target, error));
+          }
+        }
+      }
+      originals.push((player, recorded));
+    }
+
+    for (player, recorded) in originals {
+      if let Some(mut player) = world.get_mut::<PropertyPlayer>(player) {
+        player.originals = recorded;
+      }
+    }
+
+    for (player, index, target, error) in failed {
+      let Some(mut player) = world.get_mut::<PropertyPlayer>(player) else {
+        continue;
+      };
+      if player.failed.insert((index, target)) {
+        warn!("{error}");
+      }
+    }
+  });
+}
+
+fn find_path(world: &World, root: Entity, path: &[String]) -> Option<Entity> {
+  let mut current = root;
+  for part in path {
+    let children = world.get::<Children>(current)?;
+    current = *children.iter().find(|child| {
+      world
+        .get::<Name>(**child)
+        .is_some_and(|name| name.as_str() == part)
+    })?;
+  }
+  Some(current)
+}
+
+fn collect_descendants(world: &World, entity: Entity, out: &mut Vec<Entity>) {
+  let Some(children) = world.get::<Children>(entity) else {
+    return;
+  };
+  for child in children.iter() {
+    out.push(*child);
+    collect_descendants(world, *child, out);
+  }
+}
+
+fn registration<'a>(registry: &'a TypeRegistry, name: &str) -> Option<&'a TypeRegistration> {
+  registry
+    .get_with_short_type_path(name)
+    .or_else(|| registry.get_with_type_path(name))
+}
+
+fn write_property(
+  world: &mut World,
+  registry: &TypeRegistry,
+  entity: Entity,
+  target: &PropertyTarget,
+  value: &PropertyValue,
+  required: bool,
+  originals: &mut Vec<Original>,
+) -> Result<(), String> {
+  let (type_name, field) = match target {
+    PropertyTarget::Component(type_name, field) | PropertyTarget::Asset(type_name, field) => {
+      (type_name, field)
+    }
+  };
+  let Some(registration) = registration(registry, type_name) else {
+    return Err(format!(
+      "Cannot animate {type_name:?}, type is not registered"
+    ));
+  };
+  let written = match target {
+    PropertyTarget::Component(..) => {
+      let Some(component) = registration.data::<ReflectComponent>() else {
+        return Err(format!(
+          "Cannot animate {type_name:?}, it is not a reflected component"
+        ));
+      };
+      let mut entity_mut = world.entity_mut(entity);
+      let Some(mut reflected) = component.reflect_mut(&mut entity_mut) else {
+        if required {
+          return Err(format!(
+            "Cannot animate {type_name:?}, not found on {entity:?}"
+          ));
+        }
+        return Ok(());
+      };
+      let component = registration.type_id();
+      let recorded = originals.iter().any(|original| {
+        matches!(original, Original::Field { entity: e, component: c, field: f, .. }
+          if *e == entity && *c == component && f == field)
+      });
+      if !recorded {
+        if let Ok(original) = reflected.reflect_path(field.as_str()) {
+          originals.push(Original::Field {
+            entity,
+            component,
+            field: field.clone(),
+            value: original.clone_value(),
+          });
+        }
+      }
+      reflected
+        .reflect_path_mut(field.as_str())
+        .is_ok_and(|f| value.write(f))
+    }
+    PropertyTarget::Asset(..) => {
+      let Some(asset) = registration.data::<ReflectAsset>() else {
+        return Err(format!(
+          "Cannot animate {type_name:?}, it is not a reflected asset"
+        ));
+      };
+      let handle_registration = registry.get(asset.handle_type_id());
+      let (Some(handle_component), Some(reflect_handle)) = (
+        handle_registration.and_then(|r| r.data::<ReflectComponent>()),
+        handle_registration.and_then(|r| r.data::<ReflectHandle>()),
+      ) else {
+        return Ok(());
+      };
+      let handle = handle_component
+        .reflect(world.entity(entity))
+        .and_then(|h| reflect_handle.downcast_handle_untyped(h.as_any()));
+      let Some(mut handle) = handle else {
+        if required {
+          return Err(format!(
+            "Cannot animate {type_name:?}, no handle found on {entity:?}"
+          ));
+        }
+        return Ok(());
+      };
+      let handle_type = asset.handle_type_id();
+      let copied = originals.iter().any(|original| {
+        matches!(original, Original::Asset { entity: e, handle_type: t, .. }
+          if *e == entity && *t == handle_type)
+      });
+      if !copied {
+        if let Some(value) = asset.get(world, handle.clone()).map(Reflect::clone_value) {
+          let copy = asset.add(world, value.as_reflect());
+          let typed = reflect_handle.typed(copy.clone());
+          handle_component.apply(&mut world.entity_mut(entity), typed.as_reflect());
+          originals.push(Original::Asset {
+            entity,
+            handle_type,
+            handle,
+            copy: copy.clone(),
+          });
+          handle = copy;
+        }
+      }
+      asset
+        .get_mut(world, handle)
+        .and_then(|a| a.reflect_path_mut(field.as_str()).ok())
+        .is_some_and(|f| value.write(f))
+    }
+  };
+  if !written {
+    return Err(format!(
+      "Cannot animate {type_name:?}, field {field:?} missing or not a {value:?}"
+    ));
+  }
+  Ok(())
+}
+
+/// Puts back a value a stopped animation changed, unless the entity is gone.
+fn restore_original(world: &mut World, registry: &TypeRegistry, original: Original) {
+  match original {
+    Original::Field {
+      entity,
+      component,
+      field,
+      value,
+    } => {
+      let component = registry
+        .get(component)
+        .and_then(|r| r.data::<ReflectComponent>());
+      let (Some(component), Some(mut entity_mut)) = (component, world.get_entity_mut(entity))
+      else {
+        return;
+      };
+      if let Some(mut reflected) = component.reflect_mut(&mut entity_mut) {
+        if let Ok(field) = reflected.reflect_path_mut(field.as_str()) {
+          field.apply(value.as_reflect());
+        }
+      }
+    }
+    Original::Asset {
+      entity,
+      handle_type,
+      handle,
+      copy,
+    } => {
+      let registration = registry.get(handle_type);
+      let (Some(component), Some(reflect_handle)) = (
+        registration.and_then(|r| r.data::<ReflectComponent>()),
+        registration.and_then(|r| r.data::<ReflectHandle>()),
+      ) else {
+        return;
+      };
+      // leave handles alone that were changed since the copy was made
+      let current = world
+        .get_entity(entity)
+        .and_then(|e| component.reflect(e))
+        .and_then(|h| reflect_handle.downcast_handle_untyped(h.as_any()));
+      if current.is_some_and(|current| current.id() == copy.id()) {
+        let typed = reflect_handle.typed(handle);
+        component.apply(&mut world.entity_mut(entity), typed.as_reflect());
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn app() -> App {
+    let mut app = App::new();
+    app
+      .add_plugins((MinimalPlugins, AssetPlugin::default()))
+      .init_asset::<PropertyAnimation>()
+      .init_asset::<StandardMaterial>()
+      .register_asset_reflect::<StandardMaterial>()
+      .register_type::<Transform>()
+      .add_systems(Update, animate_properties);
+    app
+  }
+
+  fn flash(app: &mut App) -> Handle<PropertyAnimation> {
+    let track = |property, value| PropertyTrack {
+      path: Vec::new(),
+      descendants: false,
+      property,
+      interpolation: Interpolation::Step,
+      keyframes: vec![(0.0, value)],
+    };
+    let scale = PropertyTarget::Component("Transform".to_owned(), "scale".to_owned());
+    let emissive = PropertyTarget::Asset("StandardMaterial".to_owned(), "emissive".to_owned());
+    app
+      .world
+      .resource_mut::<Assets<_>>()
+      .add(PropertyAnimation {
+        tracks: vec![
+          track(scale, PropertyValue::Vec3(2.0, 2.0, 2.0)),
+          track(emissive, PropertyValue::Color(1.0, 0.0, 0.0, 1.0)),
+        ],
+        duration: 0.0,
+      })
+  }
+
+  #[test]
+  fn shared_assets_are_copied_and_values_restored_on_stop() {
+    let mut app = app();
+    let animation = flash(&mut app);
+    let material = app
+      .world
+      .resource_mut::<Assets<StandardMaterial>>()
+      .add(StandardMaterial::default());
+    let mut player = PropertyPlayer::default();
+    player.play(animation, 1.0, true);
+    let animated = app
+      .world
+      .spawn((Transform::default(), material.clone(), player))
+      .id();
+    let other = app.world.spawn(material.clone()).id();
+    app.update();
+
+    let copy = app.world.get::<Handle<StandardMaterial>>(animated).unwrap();
+    assert_ne!(copy, &material);
+    let materials = app.world.resource::<Assets<StandardMaterial>>();
+    assert_eq!(materials.get(copy).unwrap().emissive, Color::RED);
+    assert_eq!(materials.get(&material).unwrap().emissive, Color::BLACK);
+    assert_eq!(app.world.get(other), Some(&material));
+    assert_eq!(
+      app.world.get::<Transform>(animated).unwrap().scale,
+      Vec3::splat(2.0)
+    );
+
+    let mut player = app.world.get_mut::<PropertyPlayer>(animated).unwrap();
+    player.stop();
+    app.update();
+    assert_eq!(app.world.get(animated), Some(&material));
+    assert_eq!(
+      app.world.get::<Transform>(animated).unwrap().scale,
+      Vec3::ONE
+    );
+  }
+}
@@ -0,0 +1,145 @@
+use assets::{resolve_path, RonAsset, RonAssetLoaderError};
+use bevy::{
+  asset::{ErasedLoadedAsset, LoadContext},
+  gltf::{Gltf, GltfNode},
+  prelude::*,
+  utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{basic_controller::some, clips::clip_curves};
+
+/// Maps the skeleton clips were authored for onto a differently named one. Clips are remapped
+/// once, when the controller using the map is loaded.
+#[derive(Deserialize, Serialize, Clone, Asset, TypePath)]
+pub struct RetargetMap {
+  /// Model the clips were authored for, its rest pose is what the clips are relative to
+  #[serde(default, with = "some")]
+  source: Option<String>,
+  /// Model the clips are played on
+  #[serde(default, with = "some")]
+  target: Option<String>,
+  /// Source bone name to target bone name, bones that are not listed keep their name
+  bones: HashMap<String, String>,
+  #[serde(skip)]
+  source_rest: HashMap<String, Transform>,
+  #[serde(skip)]
+  target_rest: HashMap<String, Transform>,
+}
+
+impl RetargetMap {
+  /// Copy of `clip` addressing the target bones, with every keyframe moved from the source rest
+  /// pose to the target rest pose when both models are known.
+  pub fn retarget(&self, clip: &AnimationClip) -> AnimationClip {
+    let mut retargeted = AnimationClip::default();
+    for (path, curves) in clip_curves(clip) {
+      let target = EntityPath {
+        parts: path
+          .parts
+          .iter()
+          .map(|part| match self.bones.get(part.as_str()) {
+            Some(name) => Name::new(name.clone()),
+            None => part.clone(),
+          })
+          .collect(),
+      };
+      let source_rest = path
+        .parts
+        .last()
+        .and_then(|n| self.source_rest.get(n.as_str()));
+      let target_rest = target
+        .parts
+        .last()
+        .and_then(|n| self.target_rest.get(n.as_str()));
+      for curve in curves {
+        let keyframes = match (source_rest, target_rest) {
+          (Some(from), Some(to)) => retarget_keyframes(&curve.keyframes, from, to),
+          _ => curve.keyframes.clone(),
+        };
+        retargeted.add_curve_to_path(
+          target.clone(),
+          VariableCurve {
+            keyframe_timestamps: curve.keyframe_timestamps.clone(),
+            keyframes,
+          },
+        );
+      }
+    }
+    retargeted
+  }
+}
+
+fn retarget_keyframes(keyframes: &Keyframes, from: &Transform, to: &Transform) -> Keyframes {
+  match keyframes {
+    Keyframes::Rotation(keys) => Keyframes::Rotation(
+      keys
+        .iter()
+        .map(|r| (*r * from.rotation.inverse() * to.rotation).normalize())
+        .collect(),
+    ),
+    Keyframes::Translation(keys) => {
+      // bones of different length move proportionally
+      let source_length = from.translation.length();
+      let ratio = if source_length > f32::EPSILON {
+        to.translation.length() / source_length
+      } else {
+        1.0
+      };
+      Keyframes::Translation(
+        keys
+          .iter()
+          .map(|t| to.translation + (*t - from.translation) * ratio)
+          .collect(),
+      )
+    }
+    Keyframes::Scale(keys) => {
+      Keyframes::Scale(keys.iter().map(|s| to.scale * *s / from.scale).collect())
+    }
+    Keyframes::Weights(weights) => Keyframes::Weights(weights.clone()),
+  }
+}
+
+/// Local transforms of the named nodes of a directly loaded glTF file.
+fn rest_pose(loaded: &ErasedLoadedAsset) -> Option<HashMap<String, Transform>> {
+  let gltf = loaded.get::<Gltf>()?;
+  let pose = gltf
+    .named_nodes
+    .iter()
+    .filter_map(|(name, node)| {
+      let label = node.path()?.label()?.to_owned();
+      let node = loaded.get_labeled(label)?.get::<GltfNode>()?;
+      Some((name.clone(), node.transform))
+    })
+    .collect();
+  Some(pose)
+}
+
+impl RonAsset for RetargetMap {
+  type NestedAssets = ();
+  type Settings = ();
+  fn construct_nested_assets<'a>(
+    &'a mut self,
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), RonAssetLoaderError>> {
+    Box::pin(async move {
+      for (path, rest) in [
+        (&self.source, &mut self.source_rest),
+        (&self.target, &mut self.target_rest),
+      ] {
+        let Some(path) = path else {
+          continue;
+        };
+        let path = resolve_path(load_context.asset_path(), path)?;
+        let loaded = load_context.load_direct(path.clone()).await?;
+        *rest = rest_pose(&loaded).ok_or_else(|| {
+          RonAssetLoaderError::Invalid(format!("model {path:?} is not a glTF file"))
+        })?;
+      }
+      Ok(())
+    })
+  }
+  fn extensions() -> &'static [&'static str] {
+    &["retarget.ron"]
+  }
+}
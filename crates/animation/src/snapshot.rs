@@ -0,0 +1,298 @@
+use bevy::{animation::RepeatAnimation, prelude::*, reflect::GetPath};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+  basic_controller::BasicAnimationControllerData,
+  blend::{ActiveBlend, BlendSource},
+  AnimationBlend, AnimationController, AnimationControllerData, AnimationControllerInput,
+  AnimatorTarget, Blend,
+};
+
+/// Complete animation state of an animated entity, for save games and rollback. Entities with
+/// this component have it updated every frame, a snapshot is put back with [`RestoreAnimation`].
+///
+/// Clips are referred to by asset path and bones by their names below the rig, so a snapshot can
+/// be restored on a different entity or in another session.
+#[derive(Component, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct AnimationSnapshot<D = BasicAnimationControllerData> {
+  pub parameters: BTreeMap<String, f32>,
+  pub controller: D,
+  pub clip: Option<ClipSnapshot>,
+  pub transition: Option<TransitionSnapshot>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ClipSnapshot {
+  pub path: String,
+  pub seek_time: f32,
+  pub elapsed: f32,
+  pub speed: f32,
+  /// Number of times the clip is played, `None` to repeat forever
+  pub repeat: Option<u32>,
+  pub completions: u32,
+  pub paused: bool,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TransitionSnapshot {
+  pub blend: Blend,
+  pub elapsed: f32,
+  pub source: TransitionSourceSnapshot,
+}
+
+/// What a transition blends from, see [`crate::BlendMode`].
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum TransitionSourceSnapshot {
+  /// The pose is captured on the next frame
+  Capture,
+  Clip {
+    path: String,
+    seek_time: f32,
+    speed: f32,
+    repeat: bool,
+  },
+  Pose(Vec<(Vec<String>, Transform)>),
+  Offset(Vec<(Vec<String>, Transform)>),
+}
+
+/// Puts the animation state of the entity back to the snapshot, removed once applied.
+#[derive(Component)]
+pub struct RestoreAnimation<D = BasicAnimationControllerData>(pub AnimationSnapshot<D>);
+
+impl<D: Clone + Send + Sync + 'static> AnimationSnapshot<D> {
+  /// Snapshot of `entity`, `None` if it is not animated by `T` or its rig is not found yet.
+  pub fn capture<T>(world: &World, entity: Entity) -> Option<Self>
+  where
+    T: AnimationController<ControllerData = D>,
+  {
+    let server = world.resource::<AssetServer>();
+    let input = world.get::<AnimationControllerInput>(entity)?;
+    let data = world.get::<AnimationControllerData<T>>(entity)?;
+    let rig = world.get::<AnimatorTarget>(entity)?.rig_target?;
+    let player = world.get::<AnimationPlayer>(rig)?;
+    let path = |clip: &Handle<AnimationClip>| match server.get_path(clip.id()) {
+      Some(path) => Some(path.to_string()),
+      None => {
+        if clip.id() != Handle::<AnimationClip>::default().id() {
+          warn!("Clip {clip:?} has no asset path, it is left out of the animation snapshot");
+        }
+        None
+      }
+    };
+
+    let clip = path(player.animation_clip()).map(|path| ClipSnapshot {
+      path,
+      seek_time: player.seek_time(),
+      elapsed: player.elapsed(),
+      speed: player.speed(),
+      repeat: match player.repeat_mode() {
+        RepeatAnimation::Never => Some(1),
+        RepeatAnimation::Count(n) => Some(n),
+        RepeatAnimation::Forever => None,
+      },
+      completions: player.completions(),
+      paused: player.is_paused(),
+    });
+    let transition = world
+      .get::<AnimationBlend>(entity)
+      .and_then(|blend| blend.active.as_ref())
+      .and_then(|active| {
+        let pose = |pose: &Vec<(Entity, Transform)>| {
+          pose
+            .iter()
+            .filter_map(|(bone, transform)| Some((bone_path(world, rig, *bone)?, *transform)))
+            .collect()
+        };
+        let source = match &active.source {
+          BlendSource::Capture => TransitionSourceSnapshot::Capture,
+          BlendSource::Clip {
+            clip,
+            seek_time,
+            speed,
+            repeat,
+            ..
+          } => TransitionSourceSnapshot::Clip {
+            path: path(clip)?,
+            seek_time: *seek_time,
+            speed: *speed,
+            repeat: *repeat,
+          },
+          BlendSource::Pose(bones) => TransitionSourceSnapshot::Pose(pose(bones)),
+          BlendSource::Offset(bones) => TransitionSourceSnapshot::Offset(pose(bones)),
+        };
+        Some(TransitionSnapshot {
+          blend: active.blend.clone(),
+          elapsed: active.elapsed,
+          source,
+        })
+      });
+
+    Some(Self {
+      parameters: input
+        .parameters
+        .iter()
+        .map(|(k, v)| (k.clone(), *v))
+        .collect(),
+      controller: data.data.clone(),
+      clip,
+      transition,
+    })
+  }
+
+  /// Restores the snapshot on `entity`, returns `false` if it is not animated by `T` or its rig is
+  /// not found yet. Clips that are not loaded are loaded and start playing once available.
+  pub fn restore<T>(&self, world: &mut World, entity: Entity) -> bool
+  where
+    T: AnimationController<ControllerData = D>,
+  {
+    let Some(rig) = world
+      .get::<AnimatorTarget>(entity)
+      .and_then(|t| t.rig_target)
+    else {
+      return false;
+    };
+    if !world.entity(rig).contains::<AnimationPlayer>()
+      || !world
+        .entity(entity)
+        .contains::<AnimationControllerData<T>>()
+    {
+      return false;
+    }
+    let server = world.resource::<AssetServer>().clone();
+
+    if let Some(mut input) = world.get_mut::<AnimationControllerInput>(entity) {
+      // the restored state already reflects these, they must not trigger a transition
+      input.bypass_change_detection().parameters = self
+        .parameters
+        .iter()
+        .map(|(k, v)| (k.clone(), *v))
+        .collect();
+    }
+    if let Some(mut data) = world.get_mut::<AnimationControllerData<T>>(entity) {
+      data.data = self.controller.clone();
+    }
+
+    let transition = self.transition.as_ref().map(|transition| {
+      let pose = |pose: &Vec<(Vec<String>, Transform)>| {
+        pose
+          .iter()
+          .filter_map(|(path, transform)| Some((bone_entity(world, rig, path)?, *transform)))
+          .collect()
+      };
+      let source = match &transition.source {
+        TransitionSourceSnapshot::Capture => BlendSource::Capture,
+        TransitionSourceSnapshot::Clip {
+          path,
+          seek_time,
+          speed,
+          repeat,
+        } => BlendSource::Clip {
+          clip: server.load(path),
+          seek_time: *seek_time,
+          speed: *speed,
+          repeat: *repeat,
+          bones: Vec::new(),
+        },
+        TransitionSourceSnapshot::Pose(bones) => BlendSource::Pose(pose(bones)),
+        TransitionSourceSnapshot::Offset(bones) => BlendSource::Offset(pose(bones)),
+      };
+      ActiveBlend {
+        blend: transition.blend.clone(),
+        elapsed: transition.elapsed,
+        source,
+      }
+    });
+    if let Some(mut blend) = world.get_mut::<AnimationBlend>(entity) {
+      blend.active = transition;
+    }
+
+    let mut player = world.get_mut::<AnimationPlayer>(rig).unwrap();
+    match &self.clip {
+      Some(clip) => {
+        player
+          .start(server.load(&clip.path))
+          .set_speed(clip.speed)
+          .set_repeat(match clip.repeat {
+            Some(n) => RepeatAnimation::Count(n),
+            None => RepeatAnimation::Forever,
+          })
+          .seek_to(clip.seek_time);
+        // not exposed through methods
+        if let Ok(elapsed) = player.path_mut::<f32>("animation.elapsed") {
+          *elapsed = clip.elapsed;
+        }
+        if let Ok(completions) = player.path_mut::<u32>("animation.completions") {
+          *completions = clip.completions;
+        }
+        if clip.paused {
+          player.pause();
+        } else {
+          player.resume();
+        }
+      }
+      None => *player = AnimationPlayer::default(),
+    }
+    true
+  }
+}
+
+/// Names of the bones from below `rig` down to `bone`.
+fn bone_path(world: &World, rig: Entity, bone: Entity) -> Option<Vec<String>> {
+  let mut path = Vec::new();
+  let mut current = bone;
+  while current != rig {
+    path.push(world.get::<Name>(current)?.as_str().to_owned());
+    current = world.get::<Parent>(current)?.get();
+  }
+  path.reverse();
+  Some(path)
+}
+
+fn bone_entity(world: &World, rig: Entity, path: &[String]) -> Option<Entity> {
+  let mut current = rig;
+  for part in path {
+    current = *world.get::<Children>(current)?.iter().find(|child| {
+      world
+        .get::<Name>(**child)
+        .is_some_and(|n| n.as_str() == part)
+    })?;
+  }
+  Some(current)
+}
+
+/// Keeps every [`AnimationSnapshot`] up to date with the state of its entity.
+pub(crate) fn capture_animation_snapshots<T>(world: &mut World)
+where
+  T: AnimationController,
+  T::ControllerData: Clone,
+{
+  let mut qry = world.query_filtered::<Entity, With<AnimationSnapshot<T::ControllerData>>>();
+  let entities: Vec<_> = qry.iter(world).collect();
+  for entity in entities {
+    if let Some(snapshot) = AnimationSnapshot::capture::<T>(world, entity) {
+      world.entity_mut(entity).insert(snapshot);
+    }
+  }
+}
+
+pub(crate) fn restore_animation_snapshots<T>(world: &mut World)
+where
+  T: AnimationController,
+  T::ControllerData: Clone,
+{
+  let mut qry = world.query_filtered::<Entity, With<RestoreAnimation<T::ControllerData>>>();
+  let entities: Vec<_> = qry.iter(world).collect();
+  for entity in entities {
+    let Some(restore) = world
+      .entity_mut(entity)
+      .take::<RestoreAnimation<T::ControllerData>>()
+    else {
+      continue;
+    };
+    // wait for the rig to be found
+    if !restore.0.restore::<T>(world, entity) {
+      world.entity_mut(entity).insert(restore);
+    }
+  }
+}
@@ -0,0 +1,115 @@
+use animation::{solve_look_at, solve_two_bone};
+use bevy::math::{Quat, Vec3};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+const HIP: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+const KNEE: Vec3 = Vec3::new(0.0, 0.5, 0.05);
+const FOOT: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+
+/// Joint positions after applying the rotations returned by `solve_two_bone`.
+fn solved(joints: [Vec3; 3], (upper, lower): (Quat, Quat)) -> [Vec3; 3] {
+  let [a, b, c] = joints;
+  let knee = a + upper * (b - a);
+  [a, knee, knee + upper * lower * (c - b)]
+}
+
+fn bend([a, b, c]: [Vec3; 3]) -> f32 {
+  PI - (a - b).angle_between(c - b)
+}
+
+#[test]
+fn reachable_target_is_reached() {
+  let joints = [HIP, KNEE, FOOT];
+  for target in [
+    Vec3::new(0.0, 0.2, 0.3),
+    Vec3::new(0.4, 0.5, 0.0),
+    Vec3::new(-0.2, 0.1, -0.1),
+  ] {
+    let [a, b, c] = solved(joints, solve_two_bone(joints, target, None, 0.0, PI));
+    assert!(c.distance(target) < 1e-3, "{c} != {target}");
+    // bones keep their length
+    assert!((a.distance(b) - HIP.distance(KNEE)).abs() < 1e-4);
+    assert!((b.distance(c) - KNEE.distance(FOOT)).abs() < 1e-4);
+  }
+}
+
+#[test]
+fn unreachable_target_straightens_towards_it() {
+  let joints = [HIP, KNEE, FOOT];
+  let target = Vec3::new(0.5, -2.0, 0.5);
+  let [a, b, c] = solved(joints, solve_two_bone(joints, target, None, 0.0, PI));
+  let length = HIP.distance(KNEE) + KNEE.distance(FOOT);
+  assert!((a.distance(c) - length).abs() < 1e-3);
+  assert!((c - a).normalize().dot((target - a).normalize()) > 0.9999);
+  assert!((b - a).normalize().dot((target - a).normalize()) > 0.9999);
+}
+
+#[test]
+fn bend_stays_within_limits() {
+  let joints = [HIP, KNEE, FOOT];
+  let (min_bend, max_bend) = (0.2, FRAC_PI_2);
+
+  // too close to the hip, the knee would have to fold further than allowed
+  let close = Vec3::new(0.0, 0.9, 0.05);
+  let result = solved(
+    joints,
+    solve_two_bone(joints, close, None, min_bend, max_bend),
+  );
+  assert!((bend(result) - max_bend).abs() < 1e-3);
+  assert!((result[2] - HIP).normalize().dot((close - HIP).normalize()) > 0.9999);
+
+  // out of reach, the knee stays slightly bent instead of locking
+  let far = Vec3::new(0.0, -2.0, 0.0);
+  let result = solved(
+    joints,
+    solve_two_bone(joints, far, None, min_bend, max_bend),
+  );
+  assert!((bend(result) - min_bend).abs() < 1e-3);
+}
+
+#[test]
+fn knee_bends_towards_pole() {
+  let joints = [HIP, KNEE, FOOT];
+  let target = Vec3::new(0.0, 0.3, 0.0);
+  for pole in [Vec3::new(1.0, 0.5, 0.0), Vec3::new(0.0, 0.5, -1.0)] {
+    let result = solve_two_bone(joints, target, Some(pole), 0.0, PI);
+    let [a, b, c] = solved(joints, result);
+    assert!(c.distance(target) < 1e-3);
+    let axis = (c - a).normalize();
+    let knee = (b - a).reject_from(axis).normalize();
+    let towards = (pole - a).reject_from(axis).normalize();
+    assert!(knee.dot(towards) > 0.999, "{knee} not towards {towards}");
+  }
+}
+
+#[test]
+fn straight_chain_bends_towards_pole() {
+  let joints = [HIP, Vec3::new(0.0, 0.5, 0.0), FOOT];
+  let target = Vec3::new(0.0, 0.4, 0.0);
+  let pole = Vec3::new(0.0, 0.5, 1.0);
+  let [a, b, c] = solved(joints, solve_two_bone(joints, target, Some(pole), 0.0, PI));
+  assert!(c.distance(target) < 1e-3);
+  assert!(b.z > 0.0 && b.x.abs() < 1e-4);
+  assert!((a.distance(b) - 0.5).abs() < 1e-4);
+}
+
+#[test]
+fn look_at_reaches_target_within_limit() {
+  let rotation = solve_look_at(Vec3::ZERO, Vec3::Z, Vec3::new(1.0, 0.0, 1.0), PI);
+  let forward = rotation * Vec3::Z;
+  assert!(forward.dot(Vec3::new(1.0, 0.0, 1.0).normalize()) > 0.9999);
+}
+
+#[test]
+fn look_at_is_limited() {
+  // directly behind, limited to a quarter turn away from forward
+  let target = Vec3::new(0.2, 0.0, -1.0);
+  let rotation = solve_look_at(Vec3::ZERO, Vec3::Z, target, FRAC_PI_4);
+  let forward = rotation * Vec3::Z;
+  assert!((forward.angle_between(Vec3::Z) - FRAC_PI_4).abs() < 1e-4);
+  // turned towards the side of the target
+  assert!(forward.x > 0.0);
+
+  let rotation = solve_look_at(Vec3::ZERO, Vec3::Z, Vec3::Z * 2.0, FRAC_PI_4);
+  assert!(rotation.angle_between(Quat::IDENTITY) < 1e-4);
+}
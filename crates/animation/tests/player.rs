@@ -0,0 +1,99 @@
+use animation::{BasicAnimationController, PropertyAnimation};
+use assets::{RonAsset, RonAssetApp};
+use bevy::{
+  asset::LoadState, gltf::GltfPlugin, prelude::*,
+  render::mesh::skinning::SkinnedMeshInverseBindposes,
+};
+use std::path::Path;
+
+const GAME_ASSETS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets");
+
+fn app(root: &Path) -> App {
+  let mut app = App::new();
+  app
+    .add_plugins((
+      MinimalPlugins,
+      AssetPlugin {
+        file_path: root.to_string_lossy().into_owned(),
+        ..default()
+      },
+    ))
+    .init_asset::<Image>()
+    .init_asset::<Mesh>()
+    .init_asset::<StandardMaterial>()
+    .init_asset::<Scene>()
+    .init_asset::<SkinnedMeshInverseBindposes>()
+    .init_asset::<AnimationClip>()
+    .add_plugins(GltfPlugin::default())
+    .register_ron_asset::<BasicAnimationController>()
+    .register_ron_asset::<PropertyAnimation>();
+  app.finish();
+  app
+}
+
+/// Loads a controller and returns the sorted paths of its nested assets.
+fn nested_paths(app: &mut App, path: &str) -> Vec<String> {
+  let server = app.world.resource::<AssetServer>().clone();
+  let handle: Handle<BasicAnimationController> = server.load(path.to_owned());
+  for _ in 0..10000 {
+    app.update();
+    match server.get_load_state(&handle) {
+      Some(LoadState::Loaded) => break,
+      Some(LoadState::Failed) => panic!("{path} failed to load"),
+      _ => std::thread::sleep(std::time::Duration::from_millis(1)),
+    }
+  }
+  let controllers = app.world.resource::<Assets<BasicAnimationController>>();
+  let controller = controllers.get(&handle).expect("controller loaded");
+  let mut paths: Vec<_> = controller
+    .nested_paths()
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+  paths.sort();
+  paths
+}
+
+#[test]
+fn player_controller_loads_against_the_game_model() {
+  let mut app = app(Path::new(GAME_ASSETS));
+  assert_eq!(
+    nested_paths(&mut app, "player.basic.anim.ron"),
+    [
+      "char.gltf",
+      "char.gltf#Animation0",
+      "char.gltf#Animation1",
+      "char.gltf#Animation2"
+    ]
+  );
+}
+
+#[test]
+fn mirrored_clips_are_tracked_as_the_clips_they_were_made_from() {
+  let root = std::env::temp_dir().join(format!("mirrored-clips-{}", std::process::id()));
+  std::fs::create_dir_all(&root).unwrap();
+  for file in ["char.gltf", "char.bin"] {
+    std::fs::copy(Path::new(GAME_ASSETS).join(file), root.join(file)).unwrap();
+  }
+  let controller = r#"(
+  model: "char.gltf",
+  nodes: {
+    ("walk"): (clip: "Walk", repeat: true, speed: 1.0),
+    ("walk_left"): (clip: "Walk", repeat: true, speed: 1.0, mirror: true),
+  },
+  default_node: ("walk"),
+)"#;
+  std::fs::write(root.join("mirrored.basic.anim.ron"), controller).unwrap();
+
+  // the mirrored clip is `mirrored.basic.anim.ron#Mirrored0`, which must not make the controller
+  // a dependency of itself
+  let mut app = app(&root);
+  let paths = nested_paths(&mut app, "mirrored.basic.anim.ron");
+  std::fs::remove_dir_all(&root).unwrap();
+  assert_eq!(paths.len(), 3);
+  assert_eq!(paths[0], "char.gltf");
+  assert!(paths[1..]
+    .iter()
+    .all(|path| path.starts_with("char.gltf#Animation")));
+  assert_eq!(paths[1], paths[2]);
+}
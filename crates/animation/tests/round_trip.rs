@@ -0,0 +1,229 @@
+use animation::{BasicAnimationController, PropertyAnimation};
+use assets::{
+  DataFormat, NestedAssetsChanged, RonAsset, RonAssetApp, RonAssetSaver, RonAssetSaverSettings,
+  RonAssetSettings,
+};
+use bevy::{
+  asset::{
+    io::{
+      memory::{Dir, MemoryAssetReader},
+      AssetSource, AssetSourceId,
+    },
+    saver::{AssetSaver, SavedAsset},
+    AssetPath, ErasedLoadedAsset, LoadState, LoadedAsset,
+  },
+  ecs::event::ManualEventReader,
+  prelude::*,
+  tasks::block_on,
+};
+use std::path::Path;
+
+const BASE: &str = r#"(
+  nodes: {
+    ("idle"): (animation: "character.glb#Animation0", repeat: true, speed: 1.0),
+  },
+  edges: [
+    (id: "start", from: Some(("idle")), to: ("run"), transition_duration_seconds: 0.2, enabled: true, conditions: [GreaterThan("speed", 0.1)]),
+  ],
+  default_node: ("idle"),
+)"#;
+
+const CONTROLLER: &str = r#"(
+  extends: "base.basic.anim.ron",
+  nodes: {
+    ("run"): (animation: "character.glb#Animation1", properties: "glow.prop.anim.ron", repeat: true, speed: 1.5),
+    ("jump"): (animation: "character.glb#Animation2", repeat: false, speed: 1.0),
+  },
+  edges: [
+    (id: "start", from: Some(("idle")), to: ("run"), transition_duration_seconds: 0.3, curve: EaseInOut, enabled: true, conditions: [GreaterThan("speed", 0.5)]),
+    (from: None, to: ("jump"), transition_duration_seconds: 0.1, enabled: true, conditions: [Trigger("jump"), Expression("grounded > 0 && !(speed < 0.1)")]),
+    (from: Some(("jump")), to: ("idle"), transition_duration_seconds: 0.25, mode: Crossfade, enabled: false, conditions: []),
+  ],
+  layout: { ("idle"): (0.0, 0.0), ("run"): (200.0, 0.0), ("jump"): (100.0, 120.0) },
+)"#;
+
+const PROPERTIES: &str = r#"(
+  tracks: [(property: Component("Transform", "scale"), keyframes: [(0.0, Vec3(1.0, 1.0, 1.0)), (0.5, Vec3(1.2, 1.2, 1.2))])],
+)"#;
+
+fn app(dir: &Dir) -> App {
+  let dir = dir.clone();
+  let mut app = App::new();
+  app
+    .register_asset_source(
+      AssetSourceId::Default,
+      AssetSource::build().with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+    )
+    .add_plugins((MinimalPlugins, AssetPlugin::default()))
+    .init_asset::<AnimationClip>()
+    .register_ron_asset::<BasicAnimationController>()
+    .register_ron_asset::<PropertyAnimation>();
+  app
+}
+
+fn load(app: &mut App, path: &str) -> BasicAnimationController {
+  let handle: Handle<BasicAnimationController> = app
+    .world
+    .resource::<AssetServer>()
+    .load_with_settings(path.to_owned(), |settings: &mut RonAssetSettings<()>| {
+      settings.strict = true
+    });
+  for _ in 0..1000 {
+    app.update();
+    match app.world.resource::<AssetServer>().get_load_state(&handle) {
+      Some(LoadState::Loaded) => break,
+      Some(LoadState::Failed) => panic!("{path} failed to load"),
+      _ => std::thread::sleep(std::time::Duration::from_millis(1)),
+    }
+  }
+  let controllers = app.world.resource::<Assets<BasicAnimationController>>();
+  controllers.get(&handle).expect("controller loaded").clone()
+}
+
+fn save(controller: BasicAnimationController, settings: &RonAssetSaverSettings) -> Vec<u8> {
+  let loaded = ErasedLoadedAsset::from(LoadedAsset::from(controller));
+  let asset = SavedAsset::<BasicAnimationController>::from_loaded(&loaded).unwrap();
+  let mut bytes = Vec::new();
+  block_on(RonAssetSaver::default().save(&mut bytes, asset, settings)).unwrap();
+  bytes
+}
+
+fn dir() -> Dir {
+  let dir = Dir::default();
+  dir.insert_asset_text(Path::new("base.basic.anim.ron"), BASE);
+  dir.insert_asset_text(Path::new("player.basic.anim.ron"), CONTROLLER);
+  dir.insert_asset_text(Path::new("glow.prop.anim.ron"), PROPERTIES);
+  dir
+}
+
+#[test]
+fn controller_round_trips_through_ron() {
+  let dir = dir();
+  let mut app = app(&dir);
+  let settings = RonAssetSaverSettings::default();
+  let saved = save(load(&mut app, "player.basic.anim.ron"), &settings);
+  let ron = String::from_utf8(saved.clone()).unwrap();
+  // the document is saved as written, without its base merged in
+  assert!(ron.contains("extends: \"base.basic.anim.ron\""), "{ron}");
+  assert!(!ron.contains("character.glb#Animation0"), "{ron}");
+  assert_eq!(ron.matches("\"start\"").count(), 1, "{ron}");
+  assert!(ron.contains("properties: \"glow.prop.anim.ron\""), "{ron}");
+  assert!(ron.contains("\n  nodes: {\n    (\"jump\"): ("), "{ron}");
+
+  dir.insert_asset(Path::new("saved.basic.anim.ron"), saved.clone());
+  let reloaded = load(&mut app, "saved.basic.anim.ron");
+  assert_eq!(save(reloaded, &settings), saved);
+}
+
+#[test]
+fn nested_paths_are_relative_to_the_document() {
+  let dir = dir();
+  dir.insert_asset_text(
+    Path::new("characters/knight.basic.anim.ron"),
+    r#"(
+      extends: "../base.basic.anim.ron",
+      nodes: {
+        ("run"): (animation: "knight.glb#Animation1", properties: "/glow.prop.anim.ron", repeat: true, speed: 1.0),
+      },
+    )"#,
+  );
+  let mut app = app(&dir);
+  let controller = load(&mut app, "characters/knight.basic.anim.ron");
+  let mut paths: Vec<_> = controller
+    .nested_paths()
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+  paths.sort();
+  // the base's clip stays relative to the base
+  assert_eq!(
+    paths,
+    [
+      "character.glb#Animation0",
+      "characters/knight.glb#Animation1",
+      "glow.prop.anim.ron"
+    ]
+  );
+  // and paths are saved as written
+  let ron = String::from_utf8(save(controller, &RonAssetSaverSettings::default())).unwrap();
+  assert!(ron.contains("extends: \"../base.basic.anim.ron\""), "{ron}");
+  assert!(
+    ron.contains("animation: \"knight.glb#Animation1\""),
+    "{ron}"
+  );
+  assert!(ron.contains("properties: \"/glow.prop.anim.ron\""), "{ron}");
+}
+
+#[test]
+fn controller_round_trips_through_every_format() {
+  let dir = dir();
+  let mut app = app(&dir);
+  let ron = RonAssetSaverSettings::default();
+  let controller = load(&mut app, "player.basic.anim.ron");
+  let expected = save(controller.clone(), &ron);
+  for format in DataFormat::ALL {
+    for pretty in [true, false] {
+      let settings = RonAssetSaverSettings {
+        format,
+        pretty,
+        ..default()
+      };
+      let path = format!(
+        "{format:?}-{pretty}.{}",
+        format.with_extension("basic.anim.ron")
+      );
+      dir.insert_asset(Path::new(&path), save(controller.clone(), &settings));
+      let reloaded = load(&mut app, &path);
+      assert_eq!(save(reloaded, &ron), expected, "{path}");
+    }
+  }
+}
+
+#[test]
+fn reload_reports_changed_nested_files() {
+  let root = std::env::temp_dir().join(format!("reload-nested-{}", std::process::id()));
+  std::fs::create_dir_all(&root).unwrap();
+  for (path, text) in [
+    ("base.basic.anim.ron", BASE),
+    ("player.basic.anim.ron", CONTROLLER),
+    ("glow.prop.anim.ron", PROPERTIES),
+  ] {
+    std::fs::write(root.join(path), text).unwrap();
+  }
+  let mut app = App::new();
+  app
+    .add_plugins((
+      MinimalPlugins,
+      AssetPlugin {
+        file_path: root.to_string_lossy().into_owned(),
+        watch_for_changes_override: Some(true),
+        ..default()
+      },
+    ))
+    .init_asset::<AnimationClip>()
+    .register_ron_asset::<BasicAnimationController>()
+    .register_ron_asset::<PropertyAnimation>();
+
+  let server = app.world.resource::<AssetServer>().clone();
+  let handle: Handle<BasicAnimationController> = server.load("player.basic.anim.ron");
+  let mut reader = ManualEventReader::<NestedAssetsChanged<BasicAnimationController>>::default();
+  let mut changes = Vec::new();
+  let start = std::time::Instant::now();
+  let mut changed = false;
+  while changes.is_empty() && start.elapsed().as_secs() < 20 {
+    app.update();
+    if !changed && server.get_load_state(&handle) == Some(LoadState::Loaded) {
+      // only the watcher tells the server the file changed
+      let glow = PROPERTIES.replace("1.2", "1.5");
+      std::fs::write(root.join("glow.prop.anim.ron"), glow).unwrap();
+      changed = true;
+    }
+    let events = app.world.resource::<Events<_>>();
+    changes.extend(reader.read(events).cloned());
+    std::thread::sleep(std::time::Duration::from_millis(5));
+  }
+  std::fs::remove_dir_all(&root).unwrap();
+  assert_eq!(changes.len(), 1);
+  assert_eq!(changes[0].id, handle.id());
+  assert_eq!(changes[0].paths, [AssetPath::from("glow.prop.anim.ron")]);
+}
@@ -0,0 +1,125 @@
+use animation::{
+  AnimatedBundle, AnimationSnapshot, AnimatorTarget, BasicAnimationController,
+  BasicAnimationControllerData,
+};
+use bevy::prelude::*;
+
+const SNAPSHOT: &str = r#"(
+  parameters: { "speed": 2.5, "grounded": 1.0 },
+  controller: (active_node: Some(("run"))),
+  clip: Some((
+    path: "character.glb#Animation2",
+    seek_time: 0.4,
+    elapsed: 3.4,
+    speed: 1.5,
+    repeat: None,
+    completions: 3,
+    paused: false,
+  )),
+  transition: Some((
+    blend: (duration_seconds: 0.3, curve: EaseInOut, mode: Inertialized),
+    elapsed: 0.1,
+    source: Offset([
+      (["Hips"], (translation: (0.0, 0.1, 0.0), rotation: (0.0, 0.0, 0.0, 1.0), scale: (1.0, 1.0, 1.0))),
+      (["Hips", "Spine"], (translation: (0.0, 0.0, 0.0), rotation: (0.0, 0.3826834, 0.0, 0.9238795), scale: (1.0, 1.0, 1.0))),
+    ]),
+  )),
+)"#;
+
+fn app() -> App {
+  let mut app = App::new();
+  app
+    .add_plugins((MinimalPlugins, AssetPlugin::default()))
+    .init_asset::<AnimationClip>();
+  app
+}
+
+/// An animated entity with a rig of two bones, returns the entity and its rig.
+fn spawn_character(world: &mut World) -> (Entity, Entity) {
+  let rig = world
+    .spawn((Name::new("Armature"), AnimationPlayer::default()))
+    .with_children(|rig| {
+      rig
+        .spawn((Name::new("Hips"), Transform::default()))
+        .with_children(|hips| {
+          hips.spawn((Name::new("Spine"), Transform::default()));
+        });
+    })
+    .id();
+  let entity = world
+    .spawn((
+      AnimatedBundle::<BasicAnimationController>::default(),
+      AnimatorTarget {
+        rig_target: Some(rig),
+      },
+    ))
+    .id();
+  (entity, rig)
+}
+
+fn capture(world: &World, entity: Entity) -> AnimationSnapshot {
+  AnimationSnapshot::capture::<BasicAnimationController>(world, entity).unwrap()
+}
+
+#[test]
+fn snapshot_round_trips_through_ron() {
+  let snapshot: AnimationSnapshot = serde_ron::from_str(SNAPSHOT).unwrap();
+  let ron = serde_ron::to_string(&snapshot).unwrap();
+  let parsed: AnimationSnapshot = serde_ron::from_str(&ron).unwrap();
+  assert_eq!(snapshot, parsed);
+}
+
+#[test]
+fn restored_state_is_captured_unchanged() {
+  let mut app = app();
+  let world = &mut app.world;
+  let (entity, rig) = spawn_character(world);
+
+  let snapshot: AnimationSnapshot = serde_ron::from_str(SNAPSHOT).unwrap();
+  assert!(snapshot.restore::<BasicAnimationController>(world, entity));
+  assert_eq!(capture(world, entity), snapshot);
+
+  let player = world.get::<AnimationPlayer>(rig).unwrap();
+  assert_eq!(player.seek_time(), 0.4);
+  assert_eq!(player.elapsed(), 3.4);
+  assert_eq!(player.completions(), 3);
+}
+
+#[test]
+fn snapshot_moves_between_characters() {
+  let mut app = app();
+  let world = &mut app.world;
+  let (first, rig) = spawn_character(world);
+  let (second, _) = spawn_character(world);
+
+  let clip = world
+    .resource::<AssetServer>()
+    .load::<AnimationClip>("character.glb#Animation0");
+  let mut player = world.get_mut::<AnimationPlayer>(rig).unwrap();
+  player.start(clip).set_speed(0.5).seek_to(1.25).pause();
+
+  let snapshot = capture(world, first);
+  assert_eq!(
+    snapshot.clip.as_ref().map(|c| c.path.as_str()),
+    Some("character.glb#Animation0")
+  );
+  assert_eq!(snapshot.controller, BasicAnimationControllerData::default());
+  assert!(snapshot.transition.is_none());
+
+  let ron = serde_ron::to_string(&snapshot).unwrap();
+  let loaded: AnimationSnapshot = serde_ron::from_str(&ron).unwrap();
+  assert!(loaded.restore::<BasicAnimationController>(world, second));
+  assert_eq!(capture(world, second), snapshot);
+}
+
+#[test]
+fn restore_waits_for_rig() {
+  let mut app = app();
+  let world = &mut app.world;
+  let entity = world
+    .spawn(AnimatedBundle::<BasicAnimationController>::default())
+    .id();
+  let snapshot: AnimationSnapshot = serde_ron::from_str(SNAPSHOT).unwrap();
+  assert!(!snapshot.restore::<BasicAnimationController>(world, entity));
+  assert!(AnimationSnapshot::capture::<BasicAnimationController>(world, entity).is_none());
+}
@@ -0,0 +1,580 @@
+use bevy::asset::AssetPath;
+use serde::{
+  de::{self, value::StrDeserializer, DeserializeOwned, IntoDeserializer, Visitor},
+  forward_to_deserialize_any,
+};
+use serde_ron::error::SpannedError;
+use std::fmt;
+
+use crate::RonAssetLoaderError;
+
+/// An error in a document of an asset, displayed with the line it is at.
+///
+/// ```text
+/// player.basic.anim.ron:22:19: unknown variant `GreaterThen` in `Condition`, expected one of ..
+///   did you mean `GreaterThan`?
+///    |
+/// 22 |     conditions: [GreaterThen("speed", 0.5)],
+///    |                  ^
+/// ```
+#[derive(Debug)]
+pub struct DocumentError {
+  /// Path of the document, which may be a base of the asset being loaded.
+  pub path: AssetPath<'static>,
+  /// Line and column of the error, counted from 1.
+  pub position: Option<(usize, usize)>,
+  /// The closest name to a misspelled field or enum variant.
+  pub suggestion: Option<String>,
+  pub error: RonAssetLoaderError,
+  /// The error is in the document as upgraded by [`RonAsset::migrations`](crate::RonAsset), which
+  /// the position and line refer to, not in the file itself.
+  pub migrated: bool,
+  message: String,
+  source_line: Option<String>,
+}
+
+impl DocumentError {
+  pub(crate) fn new(
+    error: RonAssetLoaderError,
+    path: &AssetPath,
+    bytes: &[u8],
+    migrated: bool,
+  ) -> Self {
+    let (message, position) = match &error {
+      RonAssetLoaderError::RonSpannedError(SpannedError { code, position }) => {
+        let message = match code {
+          serde_ron::Error::NoSuchEnumVariant {
+            expected,
+            found,
+            outer,
+          } => unknown("variant", found, outer.as_deref(), expected),
+          serde_ron::Error::NoSuchStructField {
+            expected,
+            found,
+            outer,
+          } => unknown("field", found, outer.as_deref(), expected),
+          code => code.to_string(),
+        };
+        (message, Some((position.line, position.col)))
+      }
+      RonAssetLoaderError::Json(e) if e.line() > 0 => {
+        let message = e.to_string();
+        let message = message
+          .rsplit_once(" at line ")
+          .map_or(&*message, |(m, _)| m);
+        (message.to_owned(), Some((e.line(), e.column())))
+      }
+      RonAssetLoaderError::Toml(e) => {
+        let position = e.span().map(|span| {
+          let before = &bytes[..span.start.min(bytes.len())];
+          let line_start = before
+            .iter()
+            .rposition(|b| *b == b'\n')
+            .map_or(0, |i| i + 1);
+          let line = before.iter().filter(|b| **b == b'\n').count() + 1;
+          (line, span.start - line_start + 1)
+        });
+        (e.message().to_owned(), position)
+      }
+      RonAssetLoaderError::UnknownField {
+        path,
+        expected,
+        position,
+      } => {
+        let found = path.rsplit('.').next().unwrap_or(path);
+        let message = match expected {
+          Some((outer, expected)) => unknown("field", found, Some(outer), expected),
+          None => format!("unknown field `{found}`"),
+        };
+        (message, *position)
+      }
+      error => (error.to_string(), None),
+    };
+    // the misspelled name comes first in "unknown variant `a`, expected `b` or `c`", which is how
+    // serde words it for other formats and how it is worded above for RON
+    let mut names = message.split('`').skip(1).step_by(2);
+    let misspelled =
+      match message.starts_with("unknown variant") || message.starts_with("unknown field") {
+        true => names.next(),
+        false => None,
+      };
+    let suggestion = misspelled.and_then(|found| closest(found, names));
+
+    let (position, source_line) = match position {
+      Some((line, column)) => match bytes.split(|b| *b == b'\n').nth(line.saturating_sub(1)) {
+        Some(text) => {
+          let text = String::from_utf8_lossy(text).trim_end().to_owned();
+          let prefix = text.get(..column.saturating_sub(1)).unwrap_or(&text);
+          // errors are reported after a misspelled name, point at its start instead
+          let prefix = misspelled
+            .and_then(|found| prefix.trim_end_matches([' ', '"']).strip_suffix(found))
+            .unwrap_or(prefix);
+          // the column counts bytes, the caret is placed by characters
+          let column = prefix.chars().count() + 1;
+          (Some((line, column)), Some(text))
+        }
+        None => (Some((line, column)), None),
+      },
+      None => (None, None),
+    };
+    DocumentError {
+      path: path.clone_owned(),
+      position,
+      suggestion,
+      error,
+      migrated,
+      message,
+      source_line,
+    }
+  }
+}
+
+impl fmt::Display for DocumentError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.path)?;
+    if let Some((line, column)) = self.position {
+      write!(f, ":{line}:{column}")?;
+    }
+    write!(f, ": {}", self.message)?;
+    if self.migrated {
+      write!(f, "\n  in the document as migrated to the latest version")?;
+    }
+    if let Some(suggestion) = &self.suggestion {
+      write!(f, "\n  did you mean `{suggestion}`?")?;
+    }
+    if let (Some((line, column)), Some(text)) = (self.position, &self.source_line) {
+      let gutter = " ".repeat(line.to_string().len());
+      // keep tabs so the caret lines up with the text above it
+      let indent: String = text
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+      write!(f, "\n{gutter} |\n{line} | {text}\n{gutter} | {indent}^")?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for DocumentError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    Some(&self.error)
+  }
+}
+
+/// Worded like serde's own error for unknown names.
+fn unknown(kind: &str, found: &str, outer: Option<&str>, expected: &[&str]) -> String {
+  let outer = outer.map_or(String::new(), |outer| format!(" in `{outer}`"));
+  let expected = match expected {
+    [] => "there are none".to_owned(),
+    [name] => format!("expected `{name}`"),
+    names => {
+      let names: Vec<_> = names.iter().map(|name| format!("`{name}`")).collect();
+      format!("expected one of {}", names.join(", "))
+    }
+  };
+  format!("unknown {kind} `{found}`{outer}, {expected}")
+}
+
+/// The name closest to a misspelled `found`, if any is close enough to be what was meant.
+fn closest<'a>(found: &str, names: impl Iterator<Item = &'a str>) -> Option<String> {
+  names
+    .map(|name| {
+      (
+        edit_distance(&found.to_lowercase(), &name.to_lowercase()),
+        name,
+      )
+    })
+    .filter(|(distance, _)| *distance <= (found.chars().count() / 3).max(1))
+    .min_by_key(|(distance, _)| *distance)
+    .map(|(_, name)| name.to_owned())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+  for (i, ca) in a.chars().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+    for (j, cb) in b.iter().enumerate() {
+      let substitute = diagonal + usize::from(ca != *cb);
+      diagonal = row[j + 1];
+      row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+    }
+  }
+  row[b.len()]
+}
+
+/// A step of the path to a field that strict mode found unknown.
+pub(crate) enum PathSegment {
+  Key(String),
+  Index,
+  /// `Some` or a newtype
+  Inner,
+}
+
+impl PathSegment {
+  pub(crate) fn of(path: &serde_ignored::Path) -> Vec<PathSegment> {
+    let (parent, segment) = match path {
+      serde_ignored::Path::Root => return Vec::new(),
+      serde_ignored::Path::Map { parent, key } => (parent, PathSegment::Key(key.clone())),
+      serde_ignored::Path::Seq { parent, .. } => (parent, PathSegment::Index),
+      serde_ignored::Path::Some { parent }
+      | serde_ignored::Path::NewtypeStruct { parent }
+      | serde_ignored::Path::NewtypeVariant { parent } => (parent, PathSegment::Inner),
+    };
+    let mut segments = Self::of(parent);
+    segments.push(segment);
+    segments
+  }
+}
+
+/// Name and fields of the struct of `T` at `path`, found by deserializing a document that only
+/// has the keys along the path. Structs inside enums and flattened structs are not found.
+pub(crate) fn expected_fields<T: DeserializeOwned>(
+  path: &[PathSegment],
+) -> Option<(&'static str, &'static [&'static str])> {
+  match T::deserialize(Probe(path)) {
+    Err(ProbeError::Found(name, fields)) => Some((name, fields)),
+    _ => None,
+  }
+}
+
+/// Line and column of the last key of `path` in a text document, each key searched for after the
+/// previous one.
+pub(crate) fn find_field(text: &str, path: &[PathSegment]) -> Option<(usize, usize)> {
+  let mut start = None;
+  let mut from = 0;
+  for segment in path {
+    let PathSegment::Key(key) = segment else {
+      continue;
+    };
+    let found = text[from..].match_indices(key.as_str()).find(|(i, _)| {
+      let before = text[..from + i].chars().next_back();
+      let after = text[from + i + key.len()..]
+        .trim_start_matches('"')
+        .trim_start();
+      !before.is_some_and(|c| c.is_alphanumeric() || c == '_')
+        && after.starts_with([':', '=', '.', ']'])
+    })?;
+    start = Some(from + found.0);
+    from += found.0 + key.len();
+  }
+  let before = &text[..start?];
+  let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+  Some((before.matches('\n').count() + 1, start? - line_start + 1))
+}
+
+struct Probe<'a>(&'a [PathSegment]);
+
+#[derive(Debug)]
+enum ProbeError {
+  Found(&'static str, &'static [&'static str]),
+  NotFound,
+}
+
+impl fmt::Display for ProbeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("no struct at the path")
+  }
+}
+
+impl std::error::Error for ProbeError {}
+
+impl de::Error for ProbeError {
+  fn custom<T: fmt::Display>(_msg: T) -> Self {
+    ProbeError::NotFound
+  }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Probe<'a> {
+  type Error = ProbeError;
+
+  fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, ProbeError> {
+    Err(ProbeError::NotFound)
+  }
+
+  fn deserialize_struct<V: Visitor<'de>>(
+    self,
+    name: &'static str,
+    fields: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, ProbeError> {
+    match self.0 {
+      [] => Err(ProbeError::Found(name, fields)),
+      _ => self.deserialize_map(visitor),
+    }
+  }
+
+  fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+    match self.0 {
+      [PathSegment::Key(key), rest @ ..] => visitor.visit_map(ProbeMap(Some(key), rest)),
+      _ => Err(ProbeError::NotFound),
+    }
+  }
+
+  fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+    match self.0 {
+      [PathSegment::Index, rest @ ..] => visitor.visit_seq(ProbeSeq(Some(rest))),
+      _ => Err(ProbeError::NotFound),
+    }
+  }
+
+  fn deserialize_tuple<V: Visitor<'de>>(
+    self,
+    _len: usize,
+    visitor: V,
+  ) -> Result<V::Value, ProbeError> {
+    self.deserialize_seq(visitor)
+  }
+
+  fn deserialize_tuple_struct<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    _len: usize,
+    visitor: V,
+  ) -> Result<V::Value, ProbeError> {
+    self.deserialize_seq(visitor)
+  }
+
+  fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+    match self.0 {
+      [PathSegment::Inner, rest @ ..] => visitor.visit_some(Probe(rest)),
+      _ => Err(ProbeError::NotFound),
+    }
+  }
+
+  fn deserialize_newtype_struct<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    visitor: V,
+  ) -> Result<V::Value, ProbeError> {
+    match self.0 {
+      [PathSegment::Inner, rest @ ..] => visitor.visit_newtype_struct(Probe(rest)),
+      _ => Err(ProbeError::NotFound),
+    }
+  }
+
+  forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+    bytes byte_buf unit unit_struct enum identifier ignored_any
+  }
+}
+
+/// A map with only the key along the path.
+struct ProbeMap<'a>(Option<&'a str>, &'a [PathSegment]);
+
+impl<'de, 'a> de::MapAccess<'de> for ProbeMap<'a> {
+  type Error = ProbeError;
+
+  fn next_key_seed<K: de::DeserializeSeed<'de>>(
+    &mut self,
+    seed: K,
+  ) -> Result<Option<K::Value>, ProbeError> {
+    let Some(key) = self.0.take() else {
+      return Ok(None);
+    };
+    seed
+      .deserialize(ProbeKey(key.into_deserializer()))
+      .map(Some)
+  }
+
+  fn next_value_seed<V: de::DeserializeSeed<'de>>(
+    &mut self,
+    seed: V,
+  ) -> Result<V::Value, ProbeError> {
+    seed.deserialize(Probe(self.1))
+  }
+}
+
+/// A list with only the element along the path.
+struct ProbeSeq<'a>(Option<&'a [PathSegment]>);
+
+impl<'de, 'a> de::SeqAccess<'de> for ProbeSeq<'a> {
+  type Error = ProbeError;
+
+  fn next_element_seed<T: de::DeserializeSeed<'de>>(
+    &mut self,
+    seed: T,
+  ) -> Result<Option<T::Value>, ProbeError> {
+    self
+      .0
+      .take()
+      .map(|rest| seed.deserialize(Probe(rest)))
+      .transpose()
+  }
+}
+
+/// A map key, which may also be a newtype such as a node id.
+struct ProbeKey<'a>(StrDeserializer<'a, ProbeError>);
+
+impl<'de, 'a> de::Deserializer<'de> for ProbeKey<'a> {
+  type Error = ProbeError;
+
+  fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ProbeError> {
+    self.0.deserialize_any(visitor)
+  }
+
+  fn deserialize_newtype_struct<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    visitor: V,
+  ) -> Result<V::Value, ProbeError> {
+    visitor.visit_newtype_struct(self)
+  }
+
+  forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+    bytes byte_buf option unit unit_struct seq tuple tuple_struct map struct enum identifier
+    ignored_any
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{DataFormat, RonAssetSettings};
+  use serde::Deserialize;
+  use std::collections::HashMap;
+
+  #[derive(Deserialize, Debug)]
+  #[allow(dead_code)]
+  enum Condition {
+    GreaterThan(String, f32),
+    LessThan(String, f32),
+  }
+
+  #[derive(Deserialize, Debug)]
+  #[allow(dead_code)]
+  struct Node {
+    clip: Option<String>,
+    #[serde(default)]
+    conditions: Vec<Condition>,
+  }
+
+  #[derive(Deserialize, Debug)]
+  #[allow(dead_code)]
+  struct Controller {
+    nodes: HashMap<String, Node>,
+    speed: Option<Speed>,
+  }
+
+  #[derive(Deserialize, Debug)]
+  #[allow(dead_code)]
+  struct Speed {
+    walk: f32,
+  }
+
+  fn document_error<T: DeserializeOwned + fmt::Debug>(
+    source: &str,
+    format: DataFormat,
+    strict: bool,
+  ) -> DocumentError {
+    let settings = RonAssetSettings::<()> {
+      strict,
+      ..Default::default()
+    };
+    let error = format
+      .deserialize::<T, _>(source.as_bytes(), &settings)
+      .unwrap_err();
+    DocumentError::new(error, &AssetPath::parse("a.ron"), source.as_bytes(), false)
+  }
+
+  #[test]
+  fn edit_distance_counts_edits() {
+    assert_eq!(edit_distance("", ""), 0);
+    assert_eq!(edit_distance("abc", ""), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("GreaterThen", "GreaterThan"), 1);
+    assert_eq!(edit_distance("ab", "ba"), 2);
+    assert_eq!(edit_distance("héllo", "hello"), 1);
+  }
+
+  #[test]
+  fn closest_name() {
+    let names = ["GreaterThan", "LessThan", "Trigger"];
+    let closest = |found| closest(found, names.into_iter());
+    assert_eq!(closest("GreaterThen").as_deref(), Some("GreaterThan"));
+    assert_eq!(closest("lessthan").as_deref(), Some("LessThan"));
+    assert_eq!(closest("Triger").as_deref(), Some("Trigger"));
+    // too far from any name to be a misspelling
+    assert_eq!(closest("Always"), None);
+    assert_eq!(closest("ab"), None);
+  }
+
+  #[test]
+  fn caret_points_at_the_misspelled_name() {
+    let source = r#"(
+  nodes: {
+    "idle": (
+      clip: None,
+      conditions: [GreaterThen("speed", 0.5)],
+    ),
+  },
+  speed: None,
+)"#;
+    let error = document_error::<Controller>(source, DataFormat::Ron, false);
+    assert_eq!(error.position, Some((5, 20)));
+    assert_eq!(error.suggestion.as_deref(), Some("GreaterThan"));
+    let text = error.to_string();
+    assert!(text.starts_with(
+      "a.ron:5:20: unknown variant `GreaterThen` in `Condition`, expected one of `GreaterThan`, \
+       `LessThan`\n  did you mean `GreaterThan`?"
+    ));
+    assert!(text
+      .ends_with("5 |       conditions: [GreaterThen(\"speed\", 0.5)],\n  |                    ^"));
+  }
+
+  #[test]
+  fn column_counts_characters() {
+    // the error column counts the bytes of `é`, the caret counts it once
+    let source = "(nodes: {\"é\": (clip: None, conditions: [Greater(\"a\", 1.0)])}, speed: None)";
+    let error = document_error::<Controller>(source, DataFormat::Ron, false);
+    assert_eq!(error.position, Some((1, 41)));
+    let text = error.to_string();
+    let caret = text.lines().last().unwrap();
+    assert_eq!(caret.find('^'), Some(4 + 40));
+
+    let source = "(\n\tnodes: {},\n\tspeed: Some((walk: \"fast\")),\n)";
+    let error = document_error::<Controller>(source, DataFormat::Ron, false);
+    let caret = format!("\n  | \t{}^", " ".repeat(19));
+    assert!(error.to_string().ends_with(&caret));
+  }
+
+  #[test]
+  fn unknown_fields_in_strict_mode() {
+    let source = "(\n  nodes: {\n    \"idle\": (clp: None),\n  },\n  speed: None,\n)";
+    let error = document_error::<Controller>(source, DataFormat::Ron, true);
+    assert_eq!(error.position, Some((3, 14)));
+    assert_eq!(error.suggestion.as_deref(), Some("clip"));
+    assert!(error.to_string().starts_with(
+      "a.ron:3:14: unknown field `clp` in `Node`, expected one of `clip`, `conditions`"
+    ));
+
+    let source = "(nodes: {}, speed: Some((walk: 1.0, wallk: 2.0)))";
+    let error = document_error::<Controller>(source, DataFormat::Ron, true);
+    assert_eq!(error.position, Some((1, 37)));
+    assert_eq!(error.suggestion.as_deref(), Some("walk"));
+
+    let source = "{\n  \"nodes\": {},\n  \"sped\": null\n}";
+    let error = document_error::<Controller>(source, DataFormat::Json, true);
+    assert_eq!(error.position, Some((3, 4)));
+    assert_eq!(error.suggestion.as_deref(), Some("speed"));
+
+    let source = "speed = { walk = 1.0 }\n\n[nodes.idle]\nclip = \"a\"\nclips = \"b\"\n";
+    let error = document_error::<Controller>(source, DataFormat::Toml, true);
+    assert_eq!(error.position, Some((5, 1)));
+    assert_eq!(error.suggestion.as_deref(), Some("clip"));
+  }
+
+  #[test]
+  fn migrated_documents_are_named() {
+    let error = RonAssetLoaderError::Invalid("bad".to_owned());
+    let error = DocumentError::new(error, &AssetPath::parse("a.ron"), b"()", true);
+    assert_eq!(
+      error.to_string(),
+      "a.ron: Invalid asset: bad\n  in the document as migrated to the latest version"
+    );
+  }
+}
@@ -0,0 +1,148 @@
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use std::path::Path;
+
+use crate::{
+  diagnostic::{expected_fields, find_field, PathSegment},
+  RonAssetLoaderError, RonAssetSaverError, RonAssetSaverSettings, RonAssetSettings,
+};
+
+/// Serialization format of a data asset, picked from the last extension of its path.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DataFormat {
+  #[default]
+  Ron,
+  Json,
+  Toml,
+  /// MessagePack, which unlike most binary formats is self-describing, so optional and defaulted
+  /// fields work the same as in text formats.
+  Binary,
+}
+
+impl DataFormat {
+  pub const ALL: [DataFormat; 4] = [
+    DataFormat::Ron,
+    DataFormat::Json,
+    DataFormat::Toml,
+    DataFormat::Binary,
+  ];
+
+  pub fn extension(self) -> &'static str {
+    match self {
+      DataFormat::Ron => "ron",
+      DataFormat::Json => "json",
+      DataFormat::Toml => "toml",
+      DataFormat::Binary => "bin",
+    }
+  }
+
+  /// Format of the file at `path`. Files with an unknown extension are read as RON.
+  pub fn from_path(path: &Path) -> Self {
+    let extension = path.extension().and_then(|e| e.to_str());
+    Self::ALL
+      .into_iter()
+      .find(|format| Some(format.extension()) == extension)
+      .unwrap_or(DataFormat::Ron)
+  }
+
+  /// An asset extension in this format, e.g. `anim.json` for `anim.ron`.
+  pub fn with_extension(self, extension: &str) -> String {
+    let stem = extension.strip_suffix(".ron").unwrap_or(extension);
+    format!("{stem}.{}", self.extension())
+  }
+
+  pub(crate) fn deserialize<T: DeserializeOwned, S>(
+    self,
+    bytes: &[u8],
+    settings: &RonAssetSettings<S>,
+  ) -> Result<T, RonAssetLoaderError> {
+    let mut unknown = None;
+    let mut ignored = |path: serde_ignored::Path| {
+      // every document may have a version, see `RonAsset::migrations`
+      let version = matches!(
+        &path,
+        serde_ignored::Path::Map { parent: serde_ignored::Path::Root, key } if key == "version"
+      );
+      if !version && unknown.is_none() {
+        unknown = Some((path.to_string(), PathSegment::of(&path)));
+      }
+    };
+    let strict = settings.strict;
+    let value = match self {
+      DataFormat::Ron => {
+        let mut de = serde_ron::Deserializer::from_bytes_with_options(bytes, settings.options())?;
+        let value = checked(&mut de, strict, &mut ignored).map_err(|e| de.span_error(e))?;
+        de.end().map_err(|e| de.span_error(e))?;
+        value
+      }
+      DataFormat::Json => {
+        let mut de = serde_json::Deserializer::from_slice(bytes);
+        let value = checked(&mut de, strict, &mut ignored)?;
+        de.end()?;
+        value
+      }
+      DataFormat::Toml => {
+        let source = std::str::from_utf8(bytes)
+          .map_err(|e| RonAssetLoaderError::Invalid(format!("TOML is not UTF-8: {e}")))?;
+        checked(toml::Deserializer::new(source), strict, &mut ignored)?
+      }
+      DataFormat::Binary => checked(
+        &mut rmp_serde::Deserializer::new(bytes),
+        strict,
+        &mut ignored,
+      )?,
+    };
+    match unknown {
+      Some((path, segments)) => {
+        let text = std::str::from_utf8(bytes)
+          .ok()
+          .filter(|_| self != DataFormat::Binary);
+        Err(RonAssetLoaderError::UnknownField {
+          path,
+          expected: expected_fields::<T>(&segments[..segments.len().saturating_sub(1)]),
+          position: text.and_then(|text| find_field(text, &segments)),
+        })
+      }
+      None => Ok(value),
+    }
+  }
+
+  pub(crate) fn serialize<T: Serialize>(
+    self,
+    value: &T,
+    settings: &RonAssetSaverSettings,
+  ) -> Result<Vec<u8>, RonAssetSaverError> {
+    let pretty = settings.pretty;
+    Ok(match self {
+      DataFormat::Ron if pretty => {
+        serde_ron::ser::to_string_pretty(value, settings.pretty_config())?.into_bytes()
+      }
+      DataFormat::Ron => serde_ron::to_string(value)?.into_bytes(),
+      DataFormat::Json if pretty => {
+        let mut bytes = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(settings.indentor.as_bytes());
+        value.serialize(&mut serde_json::Serializer::with_formatter(
+          &mut bytes, formatter,
+        ))?;
+        bytes
+      }
+      DataFormat::Json => serde_json::to_vec(value)?,
+      DataFormat::Toml if pretty => toml::to_string_pretty(value)?.into_bytes(),
+      DataFormat::Toml => toml::to_string(value)?.into_bytes(),
+      // with field names, so optional and defaulted fields can be left out
+      DataFormat::Binary => rmp_serde::to_vec_named(value)?,
+    })
+  }
+}
+
+/// Deserializes `T`, reporting fields it does not have to `ignored` in strict mode.
+fn checked<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+  de: D,
+  strict: bool,
+  ignored: impl FnMut(serde_ignored::Path),
+) -> Result<T, D::Error> {
+  if strict {
+    serde_ignored::deserialize(de, ignored)
+  } else {
+    T::deserialize(de)
+  }
+}
@@ -1,28 +1,72 @@
-use serde::Deserialize;
-use serde_ron::de::from_bytes;
-use std::marker::PhantomData;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_ron::{extensions::Extensions, Options};
+use std::{
+  collections::{HashMap, HashSet},
+  hash::Hash,
+  marker::PhantomData,
+  sync::{Mutex, OnceLock},
+};
 use thiserror::Error;
 
 use bevy::{
-  asset::{io::Reader, AssetApp, AssetLoader, AsyncReadExt, LoadContext},
+  asset::{
+    io::Reader, processor::AssetProcessor, AssetApp, AssetLoader, AssetPath, AsyncReadExt,
+    LoadContext, LoadDirectError, ReadAssetBytesError,
+  },
   prelude::*,
   utils::BoxedFuture,
 };
 
+mod diagnostic;
+mod format;
+mod manifest;
+mod migrate;
+mod processor;
+mod reload;
+mod saver;
+
+use reload::NestedVersions;
+
+pub use custom_derive::RonAsset;
+pub use diagnostic::DocumentError;
+pub use format::DataFormat;
+pub use manifest::{
+  AssetManifest, AssetsPreloaded, Preload, PreloadFailed, PreloadPlugin, PreloadState,
+};
+pub use migrate::{migrate, migrate_file, Migration, RonDocument};
+pub use processor::{RonAssetProcessor, RonAssetProcessorSettings};
+pub use reload::NestedAssetsChanged;
+pub use saver::{RonAssetSaver, RonAssetSaverError, RonAssetSaverSettings};
+
 pub trait RonAssetApp {
   fn register_ron_asset<A: RonAsset>(&mut self) -> &mut Self
   where
-    A: for<'a> Deserialize<'a> + RonAsset + Asset + Send + Sync + 'static;
+    A: for<'a> Deserialize<'a> + Serialize + Clone + RonAsset + Asset + Send + Sync + 'static;
 }
 
 impl RonAssetApp for App {
   fn register_ron_asset<A: RonAsset>(&mut self) -> &mut Self
   where
-    A: for<'a> Deserialize<'a> + RonAsset + Asset + Send + Sync + 'static,
+    A: for<'a> Deserialize<'a> + Serialize + Clone + RonAsset + Asset + Send + Sync + 'static,
   {
+    let loader = RonAssetLoader::<A>::default();
     self
+      .insert_resource(loader.nested.clone())
+      .add_event::<NestedAssetsChanged<A>>()
+      .add_systems(Update, reload::send_nested_changes::<A>)
       .init_asset::<A>()
-      .init_asset_loader::<RonAssetLoader<A>>()
+      .register_asset_loader(loader);
+    // only there when the asset plugin processes assets
+    if let Some(processor) = self.world.get_resource::<AssetProcessor>().cloned() {
+      self.register_asset_processor(RonAssetProcessor::<A>::new(processor));
+      for extension in A::extensions() {
+        for format in DataFormat::ALL {
+          let extension = format.with_extension(extension);
+          self.set_default_asset_processor::<RonAssetProcessor<A>>(&extension);
+        }
+      }
+    }
+    self
   }
 }
 
@@ -33,52 +77,351 @@ pub enum RonAssetLoaderError {
   Io(#[from] std::io::Error),
   #[error("Could not parse RON: {0}")]
   RonSpannedError(#[from] serde_ron::error::SpannedError),
+  #[error("Could not parse JSON: {0}")]
+  Json(#[from] serde_json::Error),
+  #[error("Could not parse TOML: {0}")]
+  Toml(#[from] toml::de::Error),
+  #[error("Could not decode MessagePack: {0}")]
+  Binary(#[from] rmp_serde::decode::Error),
+  #[error("Could not read base asset: {0}")]
+  ReadBase(#[from] ReadAssetBytesError),
+  #[error("Cyclic base asset reference: {0}")]
+  CyclicBase(AssetPath<'static>),
+  #[error("Could not load dependency: {0}")]
+  LoadDirect(Box<LoadDirectError>),
+  #[error("Invalid asset: {0}")]
+  Invalid(String),
+  #[error("Unknown field `{path}` (strict mode)")]
+  UnknownField {
+    path: String,
+    /// Name and fields of the struct the field is in, when they could be found out.
+    expected: Option<(&'static str, &'static [&'static str])>,
+    /// Line and column of the field in text formats, counted from 1.
+    position: Option<(usize, usize)>,
+  },
+  #[error("Document version {version} is newer than {latest}, the latest this build reads")]
+  NewerVersion { version: u32, latest: u32 },
+  #[error("Could not migrate from version {version}: {error}")]
+  Migration { version: u32, error: String },
+  #[error("{0}")]
+  Document(Box<DocumentError>),
+}
+
+impl From<LoadDirectError> for RonAssetLoaderError {
+  fn from(error: LoadDirectError) -> Self {
+    RonAssetLoaderError::LoadDirect(Box::new(error))
+  }
 }
 
 pub trait RonAsset {
   type NestedAssets;
+  /// Options of this asset type that can be set per file, see [`RonAssetSettings::asset`].
+  type Settings: Default + Serialize + DeserializeOwned + Send + Sync + 'static;
 
-  fn construct_nested_assets<'a>(&mut self, load_context: &'a mut LoadContext);
+  fn construct_nested_assets<'a>(
+    &'a mut self,
+    load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), RonAssetLoaderError>>;
   fn extensions() -> &'static [&'static str];
+
+  /// Called with the file's settings after its bases are merged, before nested assets are loaded.
+  fn apply_settings(&mut self, _settings: &Self::Settings) -> Result<(), RonAssetLoaderError> {
+    Ok(())
+  }
+  /// Paths of documents of the same type this one is layered on top of, in merge order, resolved
+  /// like nested asset paths with [`resolve_path`].
+  fn bases(&self) -> Vec<String> {
+    Vec::new()
+  }
+  /// Called on a copy of the asset before [`RonAssetSaver`] writes it, e.g. to undo merging in
+  /// its bases.
+  fn prepare_save(&mut self) {}
+  /// Upgrades documents written for older versions of this type, the one at index `n` turning
+  /// version `n` into `n + 1`. A document's version is its optional top level `version` field, 0
+  /// when missing, and the latest version is the number of migrations.
+  fn migrations() -> &'static [Migration] {
+    &[]
+  }
+  /// Problems a loaded asset should not ship with, which fail [`RonAssetProcessor`].
+  fn validate(&self) -> Vec<String> {
+    Vec::new()
+  }
+  /// Paths of the nested assets it holds handles to, whose files must exist for it to be
+  /// processed.
+  fn nested_paths(&self) -> Vec<AssetPath<'static>> {
+    Vec::new()
+  }
+  /// Resolves the paths of nested assets written in the document at `document` with
+  /// [`resolve_path`]. Called on every document before its bases are merged, so paths in a base
+  /// stay relative to the base.
+  fn resolve_paths(&mut self, _document: &AssetPath) -> Result<(), RonAssetLoaderError> {
+    Ok(())
+  }
+  /// Merge this document over `base`, which already has its own bases applied.
+  fn merge_onto(&mut self, _base: Self)
+  where
+    Self: Sized,
+  {
+  }
+}
+
+/// A field holding asset paths, which `#[ron_asset(load = ..)]` loads into handles.
+pub trait NestedPath: Sized {
+  type Handle<A: Asset>;
+
+  /// Loads the paths, written in the document being loaded, resolved with [`resolve_path`].
+  fn load<A: Asset>(
+    &self,
+    load_context: &mut LoadContext,
+  ) -> Result<Self::Handle<A>, RonAssetLoaderError>;
+  /// Paths of those of `handles` that have one.
+  fn handle_paths<A: Asset>(handles: &Self::Handle<A>) -> Vec<AssetPath<'static>>;
+  /// Resolves the paths, written in the document at `document`, with [`resolve_path`].
+  fn resolve(&mut self, document: &AssetPath) -> Result<(), RonAssetLoaderError>;
+}
+
+/// Resolves a path written in the document at `document`. Paths are relative to the directory of
+/// the document, unless they start with `/` to be relative to the root of the document's asset
+/// source, or name a source of their own such as `embedded://`.
+pub fn resolve_path(
+  document: &AssetPath,
+  path: &str,
+) -> Result<AssetPath<'static>, RonAssetLoaderError> {
+  document
+    .resolve_embed(path)
+    .map_err(|error| RonAssetLoaderError::Invalid(format!("invalid path {path:?}: {error}")))
+}
+
+impl NestedPath for String {
+  type Handle<A: Asset> = Handle<A>;
+
+  fn load<A: Asset>(
+    &self,
+    load_context: &mut LoadContext,
+  ) -> Result<Handle<A>, RonAssetLoaderError> {
+    let path = resolve_path(load_context.asset_path(), self)?;
+    Ok(load_context.load(path))
+  }
+  fn handle_paths<A: Asset>(handle: &Handle<A>) -> Vec<AssetPath<'static>> {
+    handle.path().cloned().into_iter().collect()
+  }
+  fn resolve(&mut self, document: &AssetPath) -> Result<(), RonAssetLoaderError> {
+    *self = resolve_path(document, self)?.to_string();
+    Ok(())
+  }
+}
+
+impl<T: NestedPath> NestedPath for Option<T> {
+  type Handle<A: Asset> = Option<T::Handle<A>>;
+
+  fn load<A: Asset>(
+    &self,
+    load_context: &mut LoadContext,
+  ) -> Result<Self::Handle<A>, RonAssetLoaderError> {
+    self
+      .as_ref()
+      .map(|path| path.load(load_context))
+      .transpose()
+  }
+  fn handle_paths<A: Asset>(handles: &Self::Handle<A>) -> Vec<AssetPath<'static>> {
+    handles.iter().flat_map(T::handle_paths).collect()
+  }
+  fn resolve(&mut self, document: &AssetPath) -> Result<(), RonAssetLoaderError> {
+    self.iter_mut().try_for_each(|path| path.resolve(document))
+  }
 }
 
+impl<T: NestedPath> NestedPath for Vec<T> {
+  type Handle<A: Asset> = Vec<T::Handle<A>>;
+
+  fn load<A: Asset>(
+    &self,
+    load_context: &mut LoadContext,
+  ) -> Result<Self::Handle<A>, RonAssetLoaderError> {
+    self.iter().map(|path| path.load(load_context)).collect()
+  }
+  fn handle_paths<A: Asset>(handles: &Self::Handle<A>) -> Vec<AssetPath<'static>> {
+    handles.iter().flat_map(T::handle_paths).collect()
+  }
+  fn resolve(&mut self, document: &AssetPath) -> Result<(), RonAssetLoaderError> {
+    self.iter_mut().try_for_each(|path| path.resolve(document))
+  }
+}
+
+impl<K: Clone + Eq + Hash, T: NestedPath> NestedPath for HashMap<K, T> {
+  type Handle<A: Asset> = HashMap<K, T::Handle<A>>;
+
+  fn load<A: Asset>(
+    &self,
+    load_context: &mut LoadContext,
+  ) -> Result<Self::Handle<A>, RonAssetLoaderError> {
+    self
+      .iter()
+      .map(|(key, path)| Ok((key.clone(), path.load(load_context)?)))
+      .collect()
+  }
+  fn handle_paths<A: Asset>(handles: &Self::Handle<A>) -> Vec<AssetPath<'static>> {
+    handles.values().flat_map(T::handle_paths).collect()
+  }
+  fn resolve(&mut self, document: &AssetPath) -> Result<(), RonAssetLoaderError> {
+    self
+      .values_mut()
+      .try_for_each(|path| path.resolve(document))
+  }
+}
+
+/// Settings of [`RonAssetLoader`], set in an asset's `.meta` file or with
+/// `AssetServer::load_with_settings`. Bases of a document are read with the same settings, the
+/// RON extensions only apply to RON documents.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default, bound(deserialize = "S: Deserialize<'de> + Default"))]
+pub struct RonAssetSettings<S> {
+  /// Format of the document, instead of picking it from the extension of its path.
+  pub format: Option<DataFormat>,
+  /// Fail on fields the asset type does not have instead of ignoring them.
+  pub strict: bool,
+  /// RON extensions enabled as if the document started with `#![enable(..)]`.
+  pub implicit_some: bool,
+  pub unwrap_newtypes: bool,
+  pub unwrap_variant_newtypes: bool,
+  /// Settings of the asset type itself.
+  pub asset: S,
+}
+
+impl<S> RonAssetSettings<S> {
+  pub(crate) fn options(&self) -> Options {
+    let mut extensions = Extensions::empty();
+    extensions.set(Extensions::IMPLICIT_SOME, self.implicit_some);
+    extensions.set(Extensions::UNWRAP_NEWTYPES, self.unwrap_newtypes);
+    extensions.set(
+      Extensions::UNWRAP_VARIANT_NEWTYPES,
+      self.unwrap_variant_newtypes,
+    );
+    Options::default().with_default_extension(extensions)
+  }
+}
+
+/// Loads a [`RonAsset`] from RON, or from any other [`DataFormat`] when the `.ron` of one of its
+/// extensions is replaced with that format's extension, e.g. `anim.json` for `anim.ron`. The files
+/// of its nested assets are loader dependencies, so changing one reloads it and rebuilds its
+/// nested assets, see [`NestedAssetsChanged`].
 pub struct RonAssetLoader<T> {
+  extensions: Vec<&'static str>,
+  nested: NestedVersions<T>,
   phantom: PhantomData<T>,
 }
 
-impl<T> Default for RonAssetLoader<T> {
+impl<T: RonAsset> Default for RonAssetLoader<T> {
   fn default() -> Self {
+    let extensions = T::extensions()
+      .iter()
+      .flat_map(|extension| DataFormat::ALL.map(|format| format.with_extension(extension)))
+      .map(interned)
+      .collect();
     RonAssetLoader {
+      extensions,
+      nested: default(),
       phantom: PhantomData,
     }
   }
 }
 
+/// Loaders hand out borrowed extensions, so they are leaked, once however many loaders are created.
+fn interned(extension: String) -> &'static str {
+  static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+  let mut interned = INTERNED.get_or_init(default).lock().unwrap();
+  if let Some(extension) = interned.get(extension.as_str()) {
+    return extension;
+  }
+  let extension = Box::leak(extension.into_boxed_str());
+  interned.insert(extension);
+  extension
+}
+
 impl<T> AssetLoader for RonAssetLoader<T>
 where
   T: for<'a> Deserialize<'a> + RonAsset + Asset + Send + Sync + 'static,
 {
   type Asset = T;
-  type Settings = ();
+  type Settings = RonAssetSettings<T::Settings>;
   type Error = RonAssetLoaderError;
 
   fn load<'a>(
     &'a self,
     reader: &'a mut Reader,
-    _settings: &'a (),
+    settings: &'a Self::Settings,
     ctx: &'a mut LoadContext,
   ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
     Box::pin(async move {
       let mut bytes = Vec::new();
       reader.read_to_end(&mut bytes).await?;
-      let mut asset = from_bytes::<T>(&bytes)?;
-      asset.construct_nested_assets(ctx);
+      let path = ctx.asset_path().clone();
+      let format = settings
+        .format
+        .unwrap_or_else(|| DataFormat::from_path(path.path()));
+      let mut asset = load_document::<T>(bytes, format, settings, ctx, &mut vec![path]).await?;
+      asset.apply_settings(&settings.asset)?;
+      asset.construct_nested_assets(ctx).await?;
+      self.nested.track(ctx, asset.nested_paths()).await;
 
       Ok(asset)
     })
   }
 
   fn extensions(&self) -> &[&str] {
-    T::extensions()
+    &self.extensions
   }
 }
+
+/// Deserializes a document, upgraded to the latest version, and merges in its bases. Bases are
+/// read through the load context so they become loader dependencies and reloading one reloads
+/// every document built on it.
+fn load_document<'a, T>(
+  bytes: Vec<u8>,
+  format: DataFormat,
+  settings: &'a RonAssetSettings<T::Settings>,
+  ctx: &'a mut LoadContext,
+  stack: &'a mut Vec<AssetPath<'static>>,
+) -> BoxedFuture<'a, Result<T, RonAssetLoaderError>>
+where
+  T: for<'de> Deserialize<'de> + RonAsset + Send + 'static,
+{
+  Box::pin(async move {
+    let path = stack.last().expect("the document is on the stack").clone();
+    let in_document = |error, bytes: &[u8], migrated| {
+      let error = DocumentError::new(error, &path, bytes, migrated);
+      RonAssetLoaderError::Document(Box::new(error))
+    };
+    let (bytes, migrated) = match migrate::upgrade::<T>(&bytes, format) {
+      Ok(Some(migrated)) => (migrated, true),
+      Ok(None) => (bytes, false),
+      Err(error) => return Err(in_document(error, &bytes, false)),
+    };
+    let mut asset = format
+      .deserialize::<T, _>(&bytes, settings)
+      .map_err(|error| in_document(error, &bytes, migrated))?;
+    asset
+      .resolve_paths(&path)
+      .map_err(|error| in_document(error, &bytes, migrated))?;
+    let mut merged: Option<T> = None;
+    for base in asset.bases() {
+      let path = resolve_path(&path, &base)?;
+      if stack.contains(&path) {
+        return Err(RonAssetLoaderError::CyclicBase(path));
+      }
+      let bytes = ctx.read_asset_bytes(&path).await?;
+      let format = DataFormat::from_path(path.path());
+      stack.push(path);
+      let mut base = load_document::<T>(bytes, format, settings, ctx, stack).await?;
+      stack.pop();
+      if let Some(previous) = merged.take() {
+        base.merge_onto(previous);
+      }
+      merged = Some(base);
+    }
+    if let Some(base) = merged {
+      asset.merge_onto(base);
+    }
+    Ok(asset)
+  })
+}
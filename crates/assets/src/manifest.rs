@@ -0,0 +1,224 @@
+use bevy::{
+  asset::{LoadContext, LoadState, LoadedUntypedAsset, RecursiveDependencyLoadState},
+  prelude::*,
+  utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{RonAsset, RonAssetApp, RonAssetLoaderError};
+
+/// Paths of assets to load before they are needed, loaded by [`Preload`].
+///
+/// ```ron
+/// (
+///   assets: ["fonts/ui.ttf"],
+///   groups: {
+///     "player": ["player.basic.anim.ron", "char.gltf#Scene0"],
+///   },
+/// )
+/// ```
+#[derive(Deserialize, Serialize, Clone, Debug, Default, Asset, TypePath)]
+pub struct AssetManifest {
+  /// Loaded whichever groups are picked.
+  #[serde(default)]
+  pub assets: Vec<String>,
+  #[serde(default)]
+  pub groups: BTreeMap<String, Vec<String>>,
+}
+
+impl RonAsset for AssetManifest {
+  type NestedAssets = ();
+  type Settings = ();
+
+  fn construct_nested_assets<'a>(
+    &'a mut self,
+    _load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), RonAssetLoaderError>> {
+    Box::pin(async { Ok(()) })
+  }
+  fn extensions() -> &'static [&'static str] {
+    &["manifest.ron"]
+  }
+}
+
+/// Registers [`AssetManifest`] and loads the assets of the [`Preload`] resource once it is
+/// inserted.
+pub struct PreloadPlugin;
+
+impl Plugin for PreloadPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .register_ron_asset::<AssetManifest>()
+      .add_event::<AssetsPreloaded>()
+      .add_event::<PreloadFailed>()
+      .add_systems(Update, update_preload.run_if(resource_exists::<Preload>()));
+  }
+}
+
+/// Sent once every asset of a [`Preload`] and all of their dependencies are loaded.
+#[derive(Event, Clone, Debug)]
+pub struct AssetsPreloaded;
+
+/// Sent once when the manifest or an asset of a [`Preload`] fails to load, or a group it asks for
+/// is not in the manifest.
+#[derive(Event, Clone, Debug)]
+pub struct PreloadFailed {
+  pub errors: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreloadState {
+  LoadingManifest,
+  Loading,
+  Loaded,
+  Failed,
+}
+
+/// Loads the assets listed in an [`AssetManifest`] and tracks their progress, including the
+/// assets they depend on such as the nested assets of a [`RonAsset`]. The loaded assets are kept
+/// alive for as long as the resource is.
+#[derive(Resource, Debug)]
+pub struct Preload {
+  manifest: Handle<AssetManifest>,
+  groups: Vec<String>,
+  handles: Vec<(String, Handle<LoadedUntypedAsset>)>,
+  state: PreloadState,
+  loaded: usize,
+  done: usize,
+  errors: Vec<String>,
+}
+
+impl Preload {
+  pub fn new(manifest: Handle<AssetManifest>) -> Self {
+    Preload {
+      manifest,
+      groups: Vec::new(),
+      handles: Vec::new(),
+      state: PreloadState::LoadingManifest,
+      loaded: 0,
+      done: 0,
+      errors: Vec::new(),
+    }
+  }
+
+  /// Load only these groups of the manifest instead of all of them.
+  pub fn with_groups(mut self, groups: impl IntoIterator<Item = impl Into<String>>) -> Self {
+    self.groups = groups.into_iter().map(Into::into).collect();
+    self
+  }
+
+  pub fn state(&self) -> PreloadState {
+    self.state
+  }
+
+  /// Number of assets to load, 0 until the manifest is loaded.
+  pub fn total(&self) -> usize {
+    self.handles.len()
+  }
+
+  /// Assets that are loaded themselves, their dependencies may still be loading.
+  pub fn loaded(&self) -> usize {
+    self.loaded
+  }
+
+  /// Assets that are loaded along with all of their dependencies.
+  pub fn done(&self) -> usize {
+    self.done
+  }
+
+  /// Progress from 0 to 1, e.g. for a loading bar.
+  pub fn progress(&self) -> f32 {
+    match (self.state, self.total()) {
+      (PreloadState::Loaded, _) => 1.0,
+      (_, 0) => 0.0,
+      (_, total) => (self.loaded + self.done) as f32 / (2 * total) as f32,
+    }
+  }
+
+  pub fn errors(&self) -> &[String] {
+    &self.errors
+  }
+
+  /// The asset loaded for `path` of the manifest, once it is loaded.
+  pub fn get(&self, path: &str, loaded: &Assets<LoadedUntypedAsset>) -> Option<UntypedHandle> {
+    let (_, handle) = self.handles.iter().find(|(p, _)| p == path)?;
+    loaded.get(handle).map(|asset| asset.handle.clone())
+  }
+
+  fn start(&mut self, manifest: &AssetManifest, server: &AssetServer) {
+    let mut paths: Vec<&String> = manifest.assets.iter().collect();
+    if self.groups.is_empty() {
+      paths.extend(manifest.groups.values().flatten());
+    }
+    for group in &self.groups {
+      match manifest.groups.get(group) {
+        Some(group) => paths.extend(group),
+        None => self
+          .errors
+          .push(format!("no group `{group}` in the manifest")),
+      }
+    }
+    for path in paths {
+      if self.handles.iter().all(|(p, _)| p != path) {
+        self.handles.push((path.clone(), server.load_untyped(path)));
+      }
+    }
+  }
+}
+
+fn update_preload(
+  mut preload: ResMut<Preload>,
+  server: Res<AssetServer>,
+  manifests: Res<Assets<AssetManifest>>,
+  mut preloaded: EventWriter<AssetsPreloaded>,
+  mut failed: EventWriter<PreloadFailed>,
+) {
+  let preload = &mut *preload;
+  match preload.state {
+    PreloadState::LoadingManifest => {
+      if let Some(manifest) = manifests.get(&preload.manifest) {
+        preload.start(manifest, &server);
+        preload.state = PreloadState::Loading;
+      } else if server.get_load_state(&preload.manifest) == Some(LoadState::Failed) {
+        let path = server.get_path(&preload.manifest);
+        let path = path.map_or("manifest".to_owned(), |path| path.to_string());
+        preload.errors.push(format!("could not load {path}"));
+      } else {
+        return;
+      }
+    }
+    PreloadState::Loading => {}
+    PreloadState::Loaded | PreloadState::Failed => return,
+  }
+
+  preload.loaded = 0;
+  preload.done = 0;
+  for (path, handle) in &preload.handles {
+    let Some((state, _, recursive)) = server.get_load_states(handle) else {
+      continue;
+    };
+    if state == LoadState::Failed {
+      preload.errors.push(format!("could not load {path}"));
+    } else if recursive == RecursiveDependencyLoadState::Failed {
+      preload
+        .errors
+        .push(format!("could not load a dependency of {path}"));
+    }
+    preload.loaded += usize::from(state == LoadState::Loaded);
+    preload.done += usize::from(recursive == RecursiveDependencyLoadState::Loaded);
+  }
+
+  if !preload.errors.is_empty() {
+    for error in &preload.errors {
+      error!("Preload failed: {error}");
+    }
+    preload.state = PreloadState::Failed;
+    failed.send(PreloadFailed {
+      errors: preload.errors.clone(),
+    });
+  } else if preload.done == preload.total() {
+    preload.state = PreloadState::Loaded;
+    preloaded.send(AssetsPreloaded);
+  }
+}
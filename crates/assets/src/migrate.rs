@@ -0,0 +1,465 @@
+use serde::{
+  de::{DeserializeOwned, IgnoredAny, MapAccess, Visitor},
+  Deserialize, Deserializer,
+};
+use serde_ron::error::{Position, SpannedError};
+use std::{fmt, path::Path, str::FromStr};
+
+use crate::{DataFormat, RonAsset, RonAssetLoaderError, RonAssetSettings};
+
+/// Upgrades a document of one version of an asset type to the next, see [`RonAsset::migrations`].
+pub type Migration = fn(&mut RonDocument) -> Result<(), String>;
+
+/// A RON document for migrations to rewrite: the fields of its top level struct, in order and with
+/// their values as written. `ron::Value` drops the names of structs and enum variants, so values
+/// are only read and written as [`serde_ron::Value`] when a migration asks for it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RonDocument {
+  /// Attributes such as `#![enable(implicit_some)]` and comments before the struct.
+  header: String,
+  name: Option<String>,
+  fields: Vec<Field>,
+  /// Comments before the closing `)`.
+  trailing: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Field {
+  /// Comments before the field.
+  comments: String,
+  name: String,
+  value: String,
+}
+
+impl RonDocument {
+  /// The struct name, if the document has one.
+  pub fn name(&self) -> Option<&str> {
+    self.name.as_deref()
+  }
+  pub fn fields(&self) -> impl Iterator<Item = &str> {
+    self.fields.iter().map(|field| &*field.name)
+  }
+  /// The RON text of a field.
+  pub fn field(&self, name: &str) -> Option<&str> {
+    let field = self.fields.iter().find(|field| field.name == name)?;
+    Some(&field.value)
+  }
+  /// A field read as a value, without the names of structs and enum variants in it.
+  pub fn value(&self, name: &str) -> Result<serde_ron::Value, String> {
+    let text = self
+      .field(name)
+      .ok_or_else(|| format!("no field `{name}`"))?;
+    serde_ron::from_str(text).map_err(|error| format!("field `{name}`: {error}"))
+  }
+  /// Sets a field to RON text, adding it after the others if it is new.
+  pub fn set_field(&mut self, name: &str, ron: impl Into<String>) {
+    let value = ron.into();
+    match self.fields.iter_mut().find(|field| field.name == name) {
+      Some(field) => field.value = value,
+      None => self.fields.push(Field {
+        comments: String::new(),
+        name: name.to_owned(),
+        value,
+      }),
+    }
+  }
+  /// Sets a field to a value, see [`RonDocument::set_field`]. Maps are written as maps, so structs
+  /// are better set as text.
+  pub fn set_value(&mut self, name: &str, value: &serde_ron::Value) -> Result<(), String> {
+    let ron = serde_ron::to_string(value).map_err(|error| error.to_string())?;
+    self.set_field(name, ron);
+    Ok(())
+  }
+  /// Removes a field, returning its RON text.
+  pub fn remove_field(&mut self, name: &str) -> Option<String> {
+    let index = self.fields.iter().position(|field| field.name == name)?;
+    Some(self.fields.remove(index).value)
+  }
+  /// Renames a field where it is, returns whether the document had it.
+  pub fn rename_field(&mut self, from: &str, to: &str) -> bool {
+    let Some(field) = self.fields.iter_mut().find(|field| field.name == from) else {
+      return false;
+    };
+    field.name = to.to_owned();
+    true
+  }
+}
+
+/// Writes the fields one per line, indented with two spaces.
+impl fmt::Display for RonDocument {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.header)?;
+    if let Some(name) = &self.name {
+      write_ident(f, name)?;
+    }
+    f.write_str("(")?;
+    for field in &self.fields {
+      for comment in field.comments.lines() {
+        write!(f, "\n  {}", comment.trim())?;
+      }
+      f.write_str("\n  ")?;
+      write_ident(f, &field.name)?;
+      write!(f, ": {},", field.value)?;
+    }
+    for comment in self.trailing.lines() {
+      write!(f, "\n  {}", comment.trim())?;
+    }
+    if !self.fields.is_empty() || !self.trailing.is_empty() {
+      f.write_str("\n")?;
+    }
+    f.write_str(")\n")
+  }
+}
+
+fn write_ident(f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+  let plain = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+  if !plain {
+    f.write_str("r#")?;
+  }
+  f.write_str(name)
+}
+
+/// Reads the top level struct; its values are only measured by `ron`, not parsed into anything.
+impl FromStr for RonDocument {
+  type Err = SpannedError;
+
+  fn from_str(source: &str) -> Result<Self, SpannedError> {
+    let error = |code, at| SpannedError {
+      code,
+      position: position(source, at),
+    };
+    let mut at = skip(source, 0)?;
+    let mut header = source[..at].trim_end().to_owned();
+    if !header.is_empty() {
+      header.push('\n');
+    }
+    let name = ident(source, at).map(|(name, end)| {
+      at = end;
+      name
+    });
+    if name.is_some() {
+      at = skip(source, at)?;
+    }
+    if !source[at..].starts_with('(') {
+      return Err(error(serde_ron::Error::ExpectedStructLike, at));
+    }
+    at += 1;
+    let mut fields = Vec::new();
+    let trailing = loop {
+      let start = at;
+      at = skip(source, start)?;
+      let comments = source[start..at].trim().to_owned();
+      if source[at..].starts_with(')') {
+        at += 1;
+        break comments;
+      }
+      let (name, end) =
+        ident(source, at).ok_or_else(|| error(serde_ron::Error::ExpectedIdentifier, at))?;
+      at = skip(source, end)?;
+      if !source[at..].starts_with(':') {
+        return Err(error(serde_ron::Error::ExpectedMapColon, at));
+      }
+      let start = skip(source, at + 1)?;
+      at = value_end(source, start)?;
+      fields.push(Field {
+        comments,
+        name,
+        value: source[start..at].trim_end().to_owned(),
+      });
+      at = skip(source, at)?;
+      if source[at..].starts_with(',') {
+        at += 1;
+      } else if !source[at..].starts_with(')') {
+        return Err(error(serde_ron::Error::ExpectedStructLikeEnd, at));
+      }
+    };
+    at = skip(source, at)?;
+    if at < source.len() {
+      return Err(error(serde_ron::Error::TrailingCharacters, at));
+    }
+    Ok(RonDocument {
+      header,
+      name,
+      fields,
+      trailing,
+    })
+  }
+}
+
+/// The line and column of a byte offset, counted from 1 and in bytes like the errors of `ron`.
+fn position(source: &str, at: usize) -> Position {
+  let before = &source[..at];
+  let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+  Position {
+    line: before.matches('\n').count() + 1,
+    col: at - line_start + 1,
+  }
+}
+
+/// Moves an error `ron` reported for `source[at..]` to its place in `source`.
+fn shift(mut error: SpannedError, source: &str, at: usize) -> SpannedError {
+  let start = position(source, at);
+  if error.position.line == 1 {
+    error.position.col += start.col - 1;
+  }
+  error.position.line += start.line - 1;
+  error
+}
+
+/// The offset of the next token at or after `at`, past whitespace, comments and attributes.
+fn skip(source: &str, at: usize) -> Result<usize, SpannedError> {
+  let rest = serde_ron::Deserializer::from_str(&source[at..])
+    .map_err(|error| shift(error, source, at))?
+    .remainder()
+    .len();
+  Ok(source.len() - rest)
+}
+
+/// The offset after the value starting at `at`.
+fn value_end(source: &str, at: usize) -> Result<usize, SpannedError> {
+  let mut de =
+    serde_ron::Deserializer::from_str(&source[at..]).map_err(|error| shift(error, source, at))?;
+  IgnoredAny::deserialize(&mut de).map_err(|error| shift(de.span_error(error), source, at))?;
+  Ok(source.len() - de.remainder().len())
+}
+
+/// The identifier at `at` without a raw `r#` prefix, and the offset after it.
+fn ident(source: &str, at: usize) -> Option<(String, usize)> {
+  let rest = &source[at..];
+  let (start, other): (usize, fn(char) -> bool) = match rest.strip_prefix("r#") {
+    Some(_) => (2, |c| c.is_ascii_alphanumeric() || "_.+-".contains(c)),
+    None => (0, |c| c.is_ascii_alphanumeric() || c == '_'),
+  };
+  let name = &rest[start..];
+  let len = name.find(|c: char| !other(c)).unwrap_or(name.len());
+  let plain_start = start == 2 || name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+  (len > 0 && plain_start).then(|| (name[..len].to_owned(), at + start + len))
+}
+
+/// The top level `version` of a document in any format, 0 when it has none. Other fields are
+/// skipped without being built.
+struct Version(u32);
+
+impl<'de> Deserialize<'de> for Version {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    struct VersionVisitor;
+
+    impl<'de> Visitor<'de> for VersionVisitor {
+      type Value = Version;
+
+      fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a struct")
+      }
+
+      fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Version, A::Error> {
+        // an identifier, as `ron` only reads field names as such
+        #[derive(Deserialize)]
+        #[serde(field_identifier, rename_all = "snake_case")]
+        enum Key {
+          Version,
+          #[serde(other)]
+          Other,
+        }
+
+        let mut version = 0;
+        while let Some(key) = map.next_key()? {
+          match key {
+            Key::Version => version = map.next_value()?,
+            Key::Other => {
+              map.next_value::<IgnoredAny>()?;
+            }
+          }
+        }
+        Ok(Version(version))
+      }
+    }
+
+    deserializer.deserialize_any(VersionVisitor)
+  }
+}
+
+/// Checks a document of `version` against the `latest` one, whether it needs to be migrated.
+fn outdated(version: u32, latest: u32) -> Result<bool, RonAssetLoaderError> {
+  match version {
+    _ if version > latest => Err(RonAssetLoaderError::NewerVersion { version, latest }),
+    _ => Ok(version < latest),
+  }
+}
+
+/// Upgrades a RON document to the latest version of `T`, `None` if it already is the latest.
+pub fn migrate<T: RonAsset>(source: &str) -> Result<Option<String>, RonAssetLoaderError> {
+  let migrations = T::migrations();
+  let latest = migrations.len() as u32;
+  if latest == 0 {
+    return Ok(None);
+  }
+  let mut document: RonDocument = source.parse()?;
+  let version = match document.field("version") {
+    Some(version) => serde_ron::from_str(version)?,
+    None => 0,
+  };
+  if !outdated(version, latest)? {
+    return Ok(None);
+  }
+  for (from, migration) in migrations.iter().enumerate().skip(version as usize) {
+    migration(&mut document).map_err(|error| RonAssetLoaderError::Migration {
+      version: from as u32,
+      error,
+    })?;
+  }
+  document.remove_field("version");
+  document.fields.insert(
+    0,
+    Field {
+      comments: String::new(),
+      name: "version".to_owned(),
+      value: latest.to_string(),
+    },
+  );
+  Ok(Some(document.to_string()))
+}
+
+/// Rewrites the RON file at `path` to the latest version of `T`. Returns whether it was outdated.
+pub fn migrate_file<T: RonAsset + DeserializeOwned>(
+  path: &Path,
+) -> Result<bool, RonAssetLoaderError> {
+  let source = std::fs::read_to_string(path)?;
+  let Some(migrated) = migrate::<T>(&source)? else {
+    return Ok(false);
+  };
+  // only write documents the loader reads
+  DataFormat::Ron.deserialize::<T, ()>(migrated.as_bytes(), &RonAssetSettings::default())?;
+  std::fs::write(path, migrated)?;
+  Ok(true)
+}
+
+/// Upgrades a document read by the loader, `None` if it is the latest version. Only RON documents
+/// can be migrated, other formats are just checked for their version.
+pub(crate) fn upgrade<T: RonAsset>(
+  bytes: &[u8],
+  format: DataFormat,
+) -> Result<Option<Vec<u8>>, RonAssetLoaderError> {
+  let latest = T::migrations().len() as u32;
+  if latest == 0 {
+    return Ok(None);
+  }
+  // only outdated documents are parsed again
+  let settings = RonAssetSettings::<()>::default();
+  let Version(version) = format.deserialize(bytes, &settings)?;
+  if !outdated(version, latest)? {
+    return Ok(None);
+  }
+  if format != DataFormat::Ron {
+    return Err(RonAssetLoaderError::Migration {
+      version,
+      error: format!("only RON documents can be migrated, not {format:?}"),
+    });
+  }
+  let source = std::str::from_utf8(bytes).map_err(|error| RonAssetLoaderError::Migration {
+    version,
+    error: format!("the document is not UTF-8: {error}"),
+  })?;
+  Ok(migrate::<T>(source)?.map(String::into_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(source: &str) -> RonDocument {
+    source.parse().unwrap()
+  }
+
+  #[test]
+  fn fields_are_kept_as_written() {
+    let source = r#"#![enable(implicit_some)]
+// a door
+Door(
+  // how wide
+  size: 2.5,
+  open_when: Above("speed", 1e-3),
+  nodes: {"a": (clip: None, r#type: Blend(0.25, 'x'))}, r#type: "a, b)",
+  /* the end */
+)"#;
+    let document = parse(source);
+    assert_eq!(document.name(), Some("Door"));
+    assert_eq!(
+      document.fields().collect::<Vec<_>>(),
+      ["size", "open_when", "nodes", "type"]
+    );
+    assert_eq!(document.field("open_when"), Some(r#"Above("speed", 1e-3)"#));
+    assert_eq!(
+      document.field("nodes"),
+      Some(r#"{"a": (clip: None, r#type: Blend(0.25, 'x'))}"#)
+    );
+    assert_eq!(document.field("type"), Some(r#""a, b)""#));
+    assert_eq!(
+      document.to_string(),
+      r#"#![enable(implicit_some)]
+// a door
+Door(
+  // how wide
+  size: 2.5,
+  open_when: Above("speed", 1e-3),
+  nodes: {"a": (clip: None, r#type: Blend(0.25, 'x'))},
+  type: "a, b)",
+  /* the end */
+)
+"#
+    );
+    assert_eq!(parse(&document.to_string()), document);
+    assert_eq!(parse("()").to_string(), "()\n");
+  }
+
+  #[test]
+  fn editing() {
+    let mut document = parse("(size: 2.5, names: [\"a\"])");
+    assert!(document.rename_field("size", "width"));
+    assert!(!document.rename_field("size", "width"));
+    assert_eq!(
+      document.value("width"),
+      Ok(serde_ron::Value::Number(2.5.into()))
+    );
+    assert!(document.value("size").is_err());
+    let names = document.value("names").unwrap();
+    document.set_value("labels", &names).unwrap();
+    assert_eq!(document.remove_field("names").as_deref(), Some("[\"a\"]"));
+    document.set_field("width", "3.0");
+    assert_eq!(
+      document.to_string(),
+      "(\n  width: 3.0,\n  labels: [\"a\"],\n)\n"
+    );
+  }
+
+  #[test]
+  fn versions() {
+    let settings = RonAssetSettings::<()>::default();
+    let version = |source: &str| {
+      DataFormat::Ron
+        .deserialize::<Version, _>(source.as_bytes(), &settings)
+        .map(|Version(version)| version)
+    };
+    assert_eq!(version("(a: Some(1))").unwrap(), 0);
+    assert_eq!(
+      version("Door(a: Above(\"x\", 1.0), version: 2)").unwrap(),
+      2
+    );
+    let json = DataFormat::Json.deserialize::<Version, _>(br#"{"version": 3}"#, &settings);
+    assert_eq!(json.unwrap().0, 3);
+  }
+
+  #[test]
+  fn error_positions() {
+    let position = |source: &str| {
+      let error = source.parse::<RonDocument>().unwrap_err();
+      (error.position.line, error.position.col)
+    };
+    assert_eq!(position("(\n  a: 1,\n  é: 2,\n)"), (3, 3));
+    assert_eq!(position("(a: 1"), (1, 6));
+    assert_eq!(position("(a 1)"), (1, 4));
+    assert_eq!(position("(\n  a: [1,\n    \"b)\n"), (3, 6));
+    assert_eq!(position("(a: 1) x"), (1, 8));
+    assert_eq!(position("[1]"), (1, 1));
+  }
+}
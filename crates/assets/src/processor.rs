@@ -0,0 +1,113 @@
+use bevy::{
+  asset::{
+    io::Writer,
+    meta::{AssetAction, AssetMeta},
+    processor::{AssetProcessor, Process, ProcessContext, ProcessError},
+    saver::{AssetSaver, SavedAsset},
+  },
+  prelude::*,
+  utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+use crate::{
+  DataFormat, RonAsset, RonAssetLoader, RonAssetLoaderError, RonAssetSaver, RonAssetSaverSettings,
+  RonAssetSettings,
+};
+
+/// Settings of [`RonAssetProcessor`], set in the `.meta` file of a source asset.
+#[derive(Serialize, Deserialize)]
+#[serde(default, bound(deserialize = "S: Deserialize<'de> + Default"))]
+pub struct RonAssetProcessorSettings<S> {
+  pub loader_settings: RonAssetSettings<S>,
+  /// How the processed asset is written, MessagePack unless set otherwise.
+  pub saver_settings: RonAssetSaverSettings,
+}
+
+impl<S: Default> Default for RonAssetProcessorSettings<S> {
+  fn default() -> Self {
+    RonAssetProcessorSettings {
+      loader_settings: default(),
+      saver_settings: RonAssetSaverSettings {
+        format: DataFormat::Binary,
+        ..default()
+      },
+    }
+  }
+}
+
+/// Processes a [`RonAsset`] for release. The asset is loaded with its bases merged, rejected if
+/// [`RonAsset::validate`] finds problems or the file of a nested asset does not exist, and written
+/// as a document the runtime loads without parsing RON. Labels of nested asset paths are not
+/// checked, `char.glb#Animation9` passes as long as `char.glb` exists, since that would mean
+/// loading every nested file while processing. Registered as the default processor of every
+/// extension of the asset by [`RonAssetApp::register_ron_asset`](crate::RonAssetApp) when the
+/// asset plugin runs the processor.
+pub struct RonAssetProcessor<T> {
+  processor: AssetProcessor,
+  phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: RonAsset> RonAssetProcessor<T> {
+  pub fn new(processor: AssetProcessor) -> Self {
+    RonAssetProcessor {
+      processor,
+      phantom: PhantomData,
+    }
+  }
+
+  /// Problems that keep `asset` from being processed, empty if there are none.
+  pub async fn problems(&self, asset: &T) -> Vec<String> {
+    let mut problems = asset.validate();
+    for path in asset.nested_paths() {
+      let exists = match self.processor.get_source(path.source().clone_owned()) {
+        Ok(source) => source.reader().read(path.path()).await.is_ok(),
+        Err(_) => false,
+      };
+      if !exists {
+        problems.push(format!("nested asset {path} does not exist"));
+      }
+    }
+    problems
+  }
+}
+
+impl<T> Process for RonAssetProcessor<T>
+where
+  T: for<'a> Deserialize<'a> + Serialize + Clone + RonAsset + Asset,
+{
+  type Settings = RonAssetProcessorSettings<T::Settings>;
+  type OutputLoader = RonAssetLoader<T>;
+
+  fn process<'a>(
+    &'a self,
+    context: &'a mut ProcessContext,
+    meta: AssetMeta<(), Self>,
+    writer: &'a mut Writer,
+  ) -> BoxedFuture<'a, Result<RonAssetSettings<T::Settings>, ProcessError>> {
+    Box::pin(async move {
+      let AssetAction::Process { settings, .. } = meta.asset else {
+        return Err(ProcessError::WrongMetaType);
+      };
+      let loader_meta = AssetMeta::<RonAssetLoader<T>, ()>::new(AssetAction::Load {
+        loader: std::any::type_name::<RonAssetLoader<T>>().to_owned(),
+        settings: settings.loader_settings,
+      });
+      let loaded = context.load_source_asset(loader_meta).await?;
+      let asset = loaded.get::<T>().ok_or(ProcessError::WrongMetaType)?;
+
+      let problems = self.problems(asset).await;
+      if !problems.is_empty() {
+        let error = RonAssetLoaderError::Invalid(problems.join("; "));
+        return Err(ProcessError::AssetSaveError(Box::new(error)));
+      }
+
+      let saved = SavedAsset::from_loaded(&loaded).ok_or(ProcessError::WrongMetaType)?;
+      RonAssetSaver::<T>::default()
+        .save(writer, saved, &settings.saver_settings)
+        .await
+        .map_err(|error| ProcessError::AssetSaveError(Box::new(error)))
+    })
+  }
+}
@@ -0,0 +1,127 @@
+use bevy::{
+  asset::{AssetPath, LoadContext},
+  prelude::*,
+  utils::HashMap,
+};
+use std::{
+  collections::hash_map::DefaultHasher,
+  hash::{Hash, Hasher},
+  marker::PhantomData,
+  sync::{Arc, Mutex},
+};
+
+/// Sent when a loaded `A` was reloaded because files of its nested assets changed, after
+/// [`RonAsset::construct_nested_assets`](crate::RonAsset::construct_nested_assets) rebuilt its
+/// nested assets. The matching `AssetEvent::Modified` is sent as well.
+#[derive(Event, Clone, Debug)]
+pub struct NestedAssetsChanged<A: Asset> {
+  pub id: AssetId<A>,
+  /// Files of nested assets that changed, were added or were removed.
+  pub paths: Vec<AssetPath<'static>>,
+}
+
+type Versions = Vec<(AssetPath<'static>, u64)>;
+
+/// Hashes of the nested files of every loaded document, by document path, written by the loader.
+#[derive(Resource)]
+pub(crate) struct NestedVersions<A> {
+  versions: Arc<Mutex<HashMap<AssetPath<'static>, Versions>>>,
+  phantom: PhantomData<fn() -> A>,
+}
+
+impl<A> Clone for NestedVersions<A> {
+  fn clone(&self) -> Self {
+    NestedVersions {
+      versions: self.versions.clone(),
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<A> Default for NestedVersions<A> {
+  fn default() -> Self {
+    NestedVersions {
+      versions: default(),
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<A> NestedVersions<A> {
+  /// Reads the files of the nested assets of the document being loaded, which makes them loader
+  /// dependencies so changing one reloads the document. Files that can not be read are left to
+  /// fail the nested asset's own load. Labeled assets of the document itself are skipped, a
+  /// document depending on itself would never finish reloading.
+  pub(crate) async fn track(
+    &self,
+    load_context: &mut LoadContext<'_>,
+    nested: Vec<AssetPath<'static>>,
+  ) {
+    let document = load_context.asset_path().without_label().into_owned();
+    let mut versions = Versions::new();
+    for path in nested {
+      let file = path.without_label().into_owned();
+      if file == document || versions.iter().any(|(tracked, _)| *tracked == file) {
+        continue;
+      }
+      if let Ok(bytes) = load_context.read_asset_bytes(&file).await {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        versions.push((file, hasher.finish()));
+      }
+    }
+    self.versions.lock().unwrap().insert(document, versions);
+  }
+}
+
+pub(crate) fn send_nested_changes<A: Asset>(
+  mut events: EventReader<AssetEvent<A>>,
+  nested: Res<NestedVersions<A>>,
+  server: Res<AssetServer>,
+  mut loaded: Local<HashMap<AssetId<A>, (AssetPath<'static>, Versions)>>,
+  mut changed: EventWriter<NestedAssetsChanged<A>>,
+) {
+  for event in events.read() {
+    let (id, modified) = match event {
+      AssetEvent::Added { id } => (*id, false),
+      AssetEvent::Modified { id } => (*id, true),
+      AssetEvent::Removed { id } => {
+        // unless the document is loaded again under another id
+        if let Some((path, _)) = loaded.remove(id) {
+          if loaded.values().all(|(other, _)| *other != path) {
+            nested.versions.lock().unwrap().remove(&path);
+          }
+        }
+        continue;
+      }
+      _ => continue,
+    };
+    let Some(path) = server.get_path(id) else {
+      continue;
+    };
+    let Some(versions) = nested.versions.lock().unwrap().get(&path).cloned() else {
+      continue;
+    };
+    let Some((_, previous)) = loaded.insert(id, (path.into_owned(), versions.clone())) else {
+      continue;
+    };
+    if !modified {
+      continue;
+    }
+    let mut paths = Vec::new();
+    let added = versions
+      .iter()
+      .filter(|version| !previous.contains(version));
+    let removed = previous
+      .iter()
+      .filter(|version| !versions.contains(version));
+    for (path, _) in added.chain(removed) {
+      if !paths.contains(path) {
+        paths.push(path.clone());
+      }
+    }
+    if !paths.is_empty() {
+      changed.send(NestedAssetsChanged { id, paths });
+    }
+  }
+}
@@ -0,0 +1,152 @@
+use bevy::{
+  asset::{
+    io::Writer,
+    saver::{AssetSaver, SavedAsset},
+    AsyncWriteExt,
+  },
+  prelude::*,
+  utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+use serde_ron::ser::PrettyConfig;
+use std::marker::PhantomData;
+use thiserror::Error;
+
+use crate::{DataFormat, RonAsset, RonAssetLoader, RonAssetSettings};
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum RonAssetSaverError {
+  #[error("Could not write asset: {0}")]
+  Io(#[from] std::io::Error),
+  #[error("Could not write RON: {0}")]
+  Ron(#[from] serde_ron::Error),
+  #[error("Could not write JSON: {0}")]
+  Json(#[from] serde_json::Error),
+  #[error("Could not write TOML: {0}")]
+  Toml(#[from] toml::ser::Error),
+  #[error("Could not encode MessagePack: {0}")]
+  Binary(#[from] rmp_serde::encode::Error),
+}
+
+/// Settings of [`RonAssetSaver`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RonAssetSaverSettings {
+  pub format: DataFormat,
+  /// Write text formats indented over several lines.
+  pub pretty: bool,
+  pub indentor: String,
+  /// Nesting depth after which pretty RON is written on a single line.
+  pub depth_limit: usize,
+  /// Write the names of structs in RON.
+  pub struct_names: bool,
+}
+
+impl Default for RonAssetSaverSettings {
+  fn default() -> Self {
+    RonAssetSaverSettings {
+      format: DataFormat::Ron,
+      pretty: true,
+      indentor: "  ".to_owned(),
+      depth_limit: usize::MAX,
+      struct_names: false,
+    }
+  }
+}
+
+impl RonAssetSaverSettings {
+  pub(crate) fn pretty_config(&self) -> PrettyConfig {
+    PrettyConfig::default()
+      .indentor(self.indentor.clone())
+      .depth_limit(self.depth_limit)
+      .struct_names(self.struct_names)
+  }
+}
+
+/// Writes a [`RonAsset`] as a document [`RonAssetLoader`] reads back, for the asset processor or
+/// editors.
+pub struct RonAssetSaver<T> {
+  phantom: PhantomData<T>,
+}
+
+impl<T> Default for RonAssetSaver<T> {
+  fn default() -> Self {
+    RonAssetSaver {
+      phantom: PhantomData,
+    }
+  }
+}
+
+impl<T: RonAsset + Serialize + Clone> RonAssetSaver<T> {
+  /// Serializes a copy of `asset` prepared with [`RonAsset::prepare_save`].
+  pub fn to_bytes(
+    asset: &T,
+    settings: &RonAssetSaverSettings,
+  ) -> Result<Vec<u8>, RonAssetSaverError> {
+    let mut document = asset.clone();
+    document.prepare_save();
+    // without a version the document would be migrated again when loaded
+    let version = T::migrations().len() as u32;
+    match settings.format {
+      _ if version == 0 => settings.format.serialize(&document, settings),
+      DataFormat::Ron => {
+        let mut bytes = settings.format.serialize(&document, settings)?;
+        // a flattened struct is written as a RON map, so add the field after the opening `(`
+        let open = bytes.iter().position(|b| *b == b'(');
+        if let Some(open) = open.filter(|open| {
+          let name = &bytes[..*open];
+          name.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_')
+        }) {
+          let field = match settings.pretty {
+            true => format!("\n{}version: {version},", settings.indentor),
+            false => format!("version:{version},"),
+          };
+          bytes.splice(open + 1..open + 1, field.into_bytes());
+        }
+        Ok(bytes)
+      }
+      format => format.serialize(
+        &Versioned {
+          version,
+          asset: &document,
+        },
+        settings,
+      ),
+    }
+  }
+}
+
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+  version: u32,
+  #[serde(flatten)]
+  asset: &'a T,
+}
+
+impl<T> AssetSaver for RonAssetSaver<T>
+where
+  T: for<'a> Deserialize<'a> + Serialize + Clone + RonAsset + Asset,
+{
+  type Asset = T;
+  type Settings = RonAssetSaverSettings;
+  type OutputLoader = RonAssetLoader<T>;
+  type Error = RonAssetSaverError;
+
+  fn save<'a>(
+    &'a self,
+    writer: &'a mut Writer,
+    asset: SavedAsset<'a, T>,
+    settings: &'a RonAssetSaverSettings,
+  ) -> BoxedFuture<'a, Result<RonAssetSettings<T::Settings>, RonAssetSaverError>> {
+    Box::pin(async move {
+      let bytes = Self::to_bytes(&asset, settings)?;
+      writer.write_all(&bytes).await?;
+      // the file keeps its name, so tell the loader which format it is in
+      Ok(RonAssetSettings {
+        format: Some(settings.format),
+        ..default()
+      })
+    })
+  }
+}
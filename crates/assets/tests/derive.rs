@@ -0,0 +1,6 @@
+#[test]
+fn derive_ron_asset() {
+  let t = trybuild::TestCases::new();
+  t.pass("tests/derive/pass_*.rs");
+  t.compile_fail("tests/derive/fail_*.rs");
+}
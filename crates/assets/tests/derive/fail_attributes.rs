@@ -0,0 +1,17 @@
+use assets::RonAsset;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize, Asset, TypePath, RonAsset)]
+#[ron_asset(extention = "level.ron")]
+struct Level {
+  #[ron_asset(lod = Scene)]
+  scene: String,
+}
+
+#[derive(RonAsset)]
+enum Shape {
+  Circle,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use assets::RonAsset;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize, Asset, TypePath, RonAsset)]
+#[ron_asset(extension = "level.ron", extension = ".lvl.ron")]
+struct Level {
+  name: String,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use assets::RonAsset;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize, Asset, TypePath, RonAsset)]
+struct Level {
+  #[ron_asset(load = Scene)]
+  scene: String,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use assets::RonAsset;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize, Asset, TypePath, RonAsset)]
+#[ron_asset(assets = handles)]
+struct Level {
+  #[ron_asset(load = Scene)]
+  scene: String,
+  #[serde(skip)]
+  handles: Vec<Handle<Scene>>,
+}
+
+fn main() {}
@@ -0,0 +1,65 @@
+use assets::{migrate, Migration, RonAsset, RonAssetSaver, RonAssetSaverSettings};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+enum Trigger {
+  Above(String, f32),
+  Always,
+}
+
+#[derive(Deserialize, Serialize, Clone, Asset, TypePath, RonAsset)]
+#[ron_asset(migrations = MIGRATIONS)]
+struct Door {
+  width: f32,
+  open_when: Vec<Trigger>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+  // version 1 renamed `size` to `width`
+  |door| match door.rename_field("size", "width") {
+    true => Ok(()),
+    false => Err("no size".to_owned()),
+  },
+  // version 2 made `open_when` a list
+  |door| {
+    let trigger = door.remove_field("open_when").ok_or("no open_when")?;
+    door.set_field("open_when", format!("[{trigger}]"));
+    Ok(())
+  },
+];
+
+fn main() {
+  assert_eq!(Door::migrations().len(), 2);
+  let old = "#![enable(implicit_some)]\n(size: 2.5, /* hinge */ open_when: Above(\"speed\", 1e-3))";
+  let migrated = migrate::<Door>(old).unwrap().unwrap();
+  assert!(migrated.starts_with("#![enable(implicit_some)]\n(\n  version: 2,\n"));
+  let door: Door = serde_ron::from_str(&migrated).unwrap();
+  assert_eq!(door.width, 2.5);
+  assert_eq!(door.open_when, [Trigger::Above("speed".to_owned(), 0.001)]);
+
+  assert!(migrate::<Door>(&migrated).unwrap().is_none());
+  let newer = migrated.replace("version: 2", "version: 3");
+  assert!(migrate::<Door>(&newer).is_err());
+  assert!(
+    migrate::<Door>("(version: 1, width: 1.0, open_when: Always)")
+      .unwrap()
+      .is_some()
+  );
+
+  // saved documents are the latest version
+  let settings = RonAssetSaverSettings::default();
+  let saved = RonAssetSaver::to_bytes(&door, &settings).unwrap();
+  let saved = String::from_utf8(saved).unwrap();
+  assert!(saved.starts_with("(\n  version: 2,\n  width: 2.5,"));
+  assert!(migrate::<Door>(&saved).unwrap().is_none());
+  let settings = RonAssetSaverSettings {
+    pretty: false,
+    ..settings
+  };
+  let saved = RonAssetSaver::to_bytes(&door, &settings).unwrap();
+  assert_eq!(
+    String::from_utf8(saved).unwrap(),
+    "(version:2,width:2.5,open_when:[Above(\"speed\",0.001)])"
+  );
+}
@@ -0,0 +1,85 @@
+use assets::{RonAsset, RonAssetApp, RonAssetSaver, RonAssetSaverSettings};
+use bevy::{
+  asset::{
+    io::{
+      memory::{Dir, MemoryAssetReader},
+      AssetSource, AssetSourceId,
+    },
+    LoadState,
+  },
+  prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+
+#[derive(Deserialize, Serialize, Clone, Asset, TypePath, RonAsset)]
+#[ron_asset(extension = "level.ron", extension = "lvl.ron", assets = handles)]
+pub struct Level {
+  name: String,
+  #[ron_asset(load = Scene)]
+  scene: String,
+  #[ron_asset(load = Font)]
+  font: Option<String>,
+  #[ron_asset(load = Image)]
+  textures: Vec<String>,
+  #[ron_asset(load = AnimationClip)]
+  clips: HashMap<String, String>,
+  #[serde(skip)]
+  handles: Option<LevelAssets>,
+}
+
+const DUNGEON: &str = r#"(
+  name: "dungeon",
+  scene: "dungeon.glb#Scene0",
+  font: Some("runes.ttf"),
+  textures: ["/shared/stone.png", "../moss.png"],
+  clips: {},
+)"#;
+
+fn main() {
+  assert_eq!(Level::extensions(), ["level.ron", "lvl.ron"]);
+
+  let dir = Dir::default();
+  dir.insert_asset_text(Path::new("levels/dungeon.level.ron"), DUNGEON);
+  let mut app = App::new();
+  app
+    .register_asset_source(
+      AssetSourceId::Default,
+      AssetSource::build().with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+    )
+    .add_plugins((MinimalPlugins, AssetPlugin::default()))
+    .init_asset::<Scene>()
+    .init_asset::<Font>()
+    .init_asset::<Image>()
+    .init_asset::<AnimationClip>()
+    .register_ron_asset::<Level>();
+  let server = app.world.resource::<AssetServer>().clone();
+  let handle: Handle<Level> = server.load("levels/dungeon.level.ron");
+  while server.get_load_state(&handle) != Some(LoadState::Loaded) {
+    assert_ne!(server.get_load_state(&handle), Some(LoadState::Failed));
+    app.update();
+  }
+  let level = app.world.resource::<Assets<Level>>().get(&handle).unwrap();
+
+  // paths are relative to the document unless rooted with `/`
+  let mut paths: Vec<_> = level
+    .nested_paths()
+    .iter()
+    .map(ToString::to_string)
+    .collect();
+  paths.sort();
+  assert_eq!(
+    paths,
+    [
+      "levels/dungeon.glb#Scene0",
+      "levels/runes.ttf",
+      "moss.png",
+      "shared/stone.png"
+    ]
+  );
+  // and are saved as written
+  let saved = RonAssetSaver::to_bytes(level, &RonAssetSaverSettings::default()).unwrap();
+  let saved = String::from_utf8(saved).unwrap();
+  assert!(saved.contains(r#"scene: "dungeon.glb#Scene0""#), "{saved}");
+  assert!(saved.contains(r#""../moss.png""#), "{saved}");
+}
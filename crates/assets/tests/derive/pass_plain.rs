@@ -0,0 +1,31 @@
+use assets::RonAsset;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize, Asset, TypePath, RonAsset)]
+struct WeaponStats {
+  damage: f32,
+}
+
+#[derive(Deserialize, Asset, TypePath, RonAsset)]
+struct Table<T: Send + Sync + TypePath + 'static> {
+  rows: Vec<T>,
+}
+
+#[derive(Deserialize, Asset, TypePath, RonAsset)]
+struct IKController {
+  iterations: u32,
+}
+
+#[derive(Deserialize, Asset, TypePath, RonAsset)]
+struct LoadHTTPResponse2D {
+  status: u16,
+}
+
+fn main() {
+  assert_eq!(WeaponStats::extensions(), ["weapon_stats.ron"]);
+  assert_eq!(Table::<f32>::extensions(), ["table.ron"]);
+  // runs of capitals are one word
+  assert_eq!(IKController::extensions(), ["ik_controller.ron"]);
+  assert_eq!(LoadHTTPResponse2D::extensions(), ["load_http_response2_d.ron"]);
+}
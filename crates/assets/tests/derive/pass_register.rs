@@ -0,0 +1,25 @@
+use assets::{RonAsset, RonAssetApp};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Serialize, Clone, Asset, TypePath, RonAsset)]
+#[ron_asset(extension = "level.ron", assets = handles)]
+pub struct Level {
+  #[ron_asset(load = Scene)]
+  scene: String,
+  #[ron_asset(load = Image)]
+  textures: Vec<String>,
+  #[ron_asset(load = AnimationClip)]
+  clips: HashMap<String, String>,
+  #[serde(skip)]
+  handles: Option<LevelAssets>,
+}
+
+fn main() {
+  // saving and processing need `Serialize + Clone`, so the generated `LevelAssets` is `Clone`
+  App::new()
+    .add_plugins((MinimalPlugins, AssetPlugin::default()))
+    .register_ron_asset::<Level>()
+    .update();
+}
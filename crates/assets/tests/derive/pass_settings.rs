@@ -0,0 +1,71 @@
+use assets::{RonAsset, RonAssetApp, RonAssetLoaderError, RonAssetSettings};
+use bevy::{
+  asset::{
+    io::{
+      memory::{Dir, MemoryAssetReader},
+      AssetSource, AssetSourceId,
+    },
+    LoadState,
+  },
+  prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Default)]
+struct ArenaSettings {
+  scale: f32,
+}
+
+#[derive(Deserialize, Serialize, Clone, Asset, TypePath, RonAsset)]
+#[ron_asset(settings = ArenaSettings, apply_settings = Arena::scale)]
+struct Arena {
+  radius: f32,
+}
+
+impl Arena {
+  fn scale(&mut self, settings: &ArenaSettings) -> Result<(), RonAssetLoaderError> {
+    self.radius *= settings.scale;
+    Ok(())
+  }
+}
+
+#[derive(Deserialize, Serialize, Clone, Asset, TypePath, RonAsset)]
+struct Plain {
+  radius: f32,
+}
+
+fn main() {
+  let _: <Plain as RonAsset>::Settings = ();
+
+  let dir = Dir::default();
+  dir.insert_asset_text(Path::new("small.arena.ron"), "(radius: 2.0)");
+  let mut app = App::new();
+  app
+    .register_asset_source(
+      AssetSourceId::Default,
+      AssetSource::build().with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+    )
+    .add_plugins((MinimalPlugins, AssetPlugin::default()))
+    .register_ron_asset::<Arena>();
+  let handle: Handle<Arena> = app.world.resource::<AssetServer>().load_with_settings(
+    "small.arena.ron",
+    |settings: &mut RonAssetSettings<ArenaSettings>| settings.asset.scale = 1.5,
+  );
+  while app.world.resource::<AssetServer>().get_load_state(&handle) != Some(LoadState::Loaded) {
+    assert_ne!(
+      app.world.resource::<AssetServer>().get_load_state(&handle),
+      Some(LoadState::Failed)
+    );
+    app.update();
+  }
+  assert_eq!(
+    app
+      .world
+      .resource::<Assets<Arena>>()
+      .get(&handle)
+      .unwrap()
+      .radius,
+    3.0
+  );
+}
@@ -0,0 +1,116 @@
+use assets::{
+  AssetsPreloaded, Preload, PreloadFailed, PreloadPlugin, PreloadState, RonAsset, RonAssetApp,
+};
+use bevy::{
+  asset::{
+    io::{
+      memory::{Dir, MemoryAssetReader},
+      AssetSource, AssetSourceId,
+    },
+    LoadedUntypedAsset,
+  },
+  ecs::event::ManualEventReader,
+  prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Deserialize, Serialize, Clone, Asset, TypePath, RonAsset)]
+#[ron_asset(extension = "note.ron")]
+struct Note {
+  text: String,
+}
+
+const MANIFEST: &str = r#"(
+  assets: ["intro.note.ron"],
+  groups: {
+    "level": ["level.note.ron"],
+    "broken": ["missing.note.ron"],
+  },
+)"#;
+
+fn app() -> App {
+  let dir = Dir::default();
+  dir.insert_asset_text(Path::new("game.manifest.ron"), MANIFEST);
+  dir.insert_asset_text(Path::new("intro.note.ron"), r#"(text: "intro")"#);
+  dir.insert_asset_text(Path::new("level.note.ron"), r#"(text: "level")"#);
+  let mut app = App::new();
+  app
+    .register_asset_source(
+      AssetSourceId::Default,
+      AssetSource::build().with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+    )
+    .add_plugins((MinimalPlugins, AssetPlugin::default(), PreloadPlugin))
+    .register_ron_asset::<Note>();
+  app
+}
+
+/// Runs the app until the preload is done, returning the errors of the event it sent.
+fn preload(app: &mut App, manifest: &str, groups: &[&str]) -> Result<(), Vec<String>> {
+  let manifest = app.world.resource::<AssetServer>().load(manifest.to_owned());
+  app.insert_resource(Preload::new(manifest).with_groups(groups.iter().copied()));
+  for _ in 0..1000 {
+    app.update();
+    let state = app.world.resource::<Preload>().state();
+    if matches!(state, PreloadState::Loaded | PreloadState::Failed) {
+      break;
+    }
+    std::thread::sleep(std::time::Duration::from_millis(1));
+  }
+  let preloaded = app.world.resource::<Events<AssetsPreloaded>>();
+  let failed = app.world.resource::<Events<PreloadFailed>>();
+  let preloaded = ManualEventReader::default().read(preloaded).count();
+  let failed: Vec<_> = ManualEventReader::default().read(failed).cloned().collect();
+  match (preloaded, failed.as_slice()) {
+    (1, []) => Ok(()),
+    (0, [failed]) => Err(failed.errors.clone()),
+    _ => panic!("expected one event, got {preloaded} loaded and {failed:?}"),
+  }
+}
+
+#[test]
+fn loads_the_picked_groups() {
+  let mut app = app();
+  assert_eq!(preload(&mut app, "game.manifest.ron", &["level"]), Ok(()));
+  let preload = app.world.resource::<Preload>();
+  assert_eq!(preload.state(), PreloadState::Loaded);
+  assert_eq!(
+    (preload.total(), preload.loaded(), preload.done()),
+    (2, 2, 2)
+  );
+  assert_eq!(preload.progress(), 1.0);
+  let loaded = app.world.resource::<Assets<LoadedUntypedAsset>>();
+  let level = preload
+    .get("level.note.ron", loaded)
+    .unwrap()
+    .typed::<Note>();
+  let notes = app.world.resource::<Assets<Note>>();
+  assert_eq!(notes.get(&level).unwrap().text, "level");
+  assert!(preload.get("missing.note.ron", loaded).is_none());
+}
+
+#[test]
+fn reports_missing_assets() {
+  let mut app = app();
+  let errors = preload(&mut app, "game.manifest.ron", &[]).unwrap_err();
+  assert_eq!(errors, ["could not load missing.note.ron"]);
+  assert_eq!(
+    app.world.resource::<Preload>().state(),
+    PreloadState::Failed
+  );
+}
+
+#[test]
+fn reports_missing_groups() {
+  let mut app = app();
+  let errors = preload(&mut app, "game.manifest.ron", &["level", "bonus"]).unwrap_err();
+  assert_eq!(errors, ["no group `bonus` in the manifest"]);
+}
+
+#[test]
+fn reports_a_missing_manifest() {
+  let mut app = app();
+  let errors = preload(&mut app, "missing.manifest.ron", &[]).unwrap_err();
+  assert_eq!(errors, ["could not load missing.manifest.ron"]);
+  assert_eq!(app.world.resource::<Preload>().total(), 0);
+}
@@ -0,0 +1,159 @@
+use assets::{
+  DataFormat, Migration, RonAsset, RonAssetApp, RonAssetLoaderError, RonAssetProcessor,
+  RonAssetProcessorSettings,
+};
+use bevy::{
+  asset::{
+    io::{
+      memory::{Dir, MemoryAssetReader},
+      AssetSource, AssetSourceBuilders, AssetSourceId,
+    },
+    processor::AssetProcessor,
+    AssetPath, LoadContext, LoadState,
+  },
+  prelude::*,
+  tasks::block_on,
+  utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Asset, TypePath)]
+struct Level {
+  scene: &'static str,
+  spawns: usize,
+}
+
+impl RonAsset for Level {
+  type NestedAssets = ();
+  type Settings = ();
+
+  fn construct_nested_assets<'a>(
+    &'a mut self,
+    _load_context: &'a mut LoadContext,
+  ) -> BoxedFuture<'a, Result<(), RonAssetLoaderError>> {
+    Box::pin(async { Ok(()) })
+  }
+  fn extensions() -> &'static [&'static str] {
+    &["level.ron"]
+  }
+  fn validate(&self) -> Vec<String> {
+    match self.spawns {
+      0 => vec!["no spawn points".to_owned()],
+      _ => Vec::new(),
+    }
+  }
+  fn nested_paths(&self) -> Vec<AssetPath<'static>> {
+    vec![AssetPath::parse(self.scene).into_owned()]
+  }
+}
+
+fn processor() -> RonAssetProcessor<Level> {
+  let dir = Dir::default();
+  dir.insert_asset_text(Path::new("levels/dungeon.glb"), "");
+  let mut sources = AssetSourceBuilders::default();
+  sources.insert(
+    AssetSourceId::Default,
+    AssetSource::build().with_reader(move || Box::new(MemoryAssetReader { root: dir.clone() })),
+  );
+  RonAssetProcessor::new(AssetProcessor::new(&mut sources))
+}
+
+#[test]
+fn problems() {
+  let processor = processor();
+  let problems = |scene, spawns| block_on(processor.problems(&Level { scene, spawns }));
+
+  assert!(problems("levels/dungeon.glb", 1).is_empty());
+  assert_eq!(problems("levels/dungeon.glb", 0), ["no spawn points"]);
+  assert_eq!(
+    problems("levels/cave.glb#Scene0", 0),
+    [
+      "no spawn points",
+      "nested asset levels/cave.glb#Scene0 does not exist"
+    ]
+  );
+  assert_eq!(
+    problems("other://levels/dungeon.glb", 1),
+    ["nested asset other://levels/dungeon.glb does not exist"]
+  );
+  // only files are checked, not the labeled assets in them
+  assert!(problems("levels/dungeon.glb#Scene9", 1).is_empty());
+}
+
+#[test]
+fn processed_assets_are_message_pack() {
+  let settings = RonAssetProcessorSettings::<()>::default();
+  assert_eq!(settings.saver_settings.format, DataFormat::Binary);
+  assert_eq!(settings.loader_settings.format, None);
+}
+
+#[derive(Deserialize, Serialize, Clone, Asset, TypePath, RonAsset)]
+#[ron_asset(migrations = ARENA_MIGRATIONS)]
+struct Arena {
+  name: String,
+  width: f32,
+  spawns: Vec<(f32, f32)>,
+}
+
+const ARENA_MIGRATIONS: &[Migration] = &[|arena| match arena.rename_field("size", "width") {
+  true => Ok(()),
+  false => Err("no size".to_owned()),
+}];
+
+#[test]
+fn processed_documents_load() {
+  // the processor keeps its log under the asset root, so everything goes to a scratch directory
+  let root = std::env::temp_dir().join(format!("assets-processor-{}", std::process::id()));
+  let _ = std::fs::remove_dir_all(&root);
+  std::fs::create_dir_all(root.join("src")).unwrap();
+  std::fs::write(
+    root.join("src/arena.arena.ron"),
+    include_str!("fixtures/arena.arena.ron"),
+  )
+  .unwrap();
+  std::env::set_var("BEVY_ASSET_ROOT", &root);
+
+  let mut app = App::new();
+  app
+    .add_plugins((
+      MinimalPlugins,
+      AssetPlugin {
+        file_path: "src".to_owned(),
+        processed_file_path: "imported_assets/Default".to_owned(),
+        watch_for_changes_override: Some(false),
+        mode: AssetMode::Processed,
+      },
+    ))
+    .register_ron_asset::<Arena>();
+  let handle: Handle<Arena> = app.world.resource::<AssetServer>().load("arena.arena.ron");
+  for _ in 0..10000 {
+    app.update();
+    match app.world.resource::<AssetServer>().get_load_state(&handle) {
+      Some(LoadState::Loaded) => break,
+      Some(LoadState::Failed) => panic!("the processed arena failed to load"),
+      _ => std::thread::sleep(std::time::Duration::from_millis(1)),
+    }
+  }
+  let arena = app
+    .world
+    .resource::<Assets<Arena>>()
+    .get(&handle)
+    .expect("arena loaded");
+  assert_eq!(arena.name, "pit");
+  assert_eq!(arena.width, 12.5);
+  assert_eq!(arena.spawns, [(0.0, 1.0), (4.0, -2.0)]);
+
+  // written as the latest version in MessagePack, which the loader is told by the meta file
+  let processed = root.join("imported_assets/Default/arena.arena.ron");
+  let bytes = std::fs::read(&processed).unwrap();
+  #[derive(Deserialize)]
+  struct Versioned {
+    version: u32,
+  }
+  let Versioned { version } = rmp_serde::from_slice(&bytes).unwrap();
+  assert_eq!(version, 1);
+  let meta = std::fs::read_to_string(processed.with_extension("ron.meta")).unwrap();
+  assert!(meta.contains("format: Some(Binary)"), "{meta}");
+  let _ = std::fs::remove_dir_all(&root);
+}
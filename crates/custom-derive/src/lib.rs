@@ -1,94 +1,242 @@
-use darling::{util::parse_expr, FromDeriveInput};
+use darling::{ast::Data, util::Ignored, FromDeriveInput, FromField};
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::*;
+use quote::{format_ident, quote, quote_spanned};
+use syn::{spanned::Spanned, DeriveInput, Ident, LitStr, Path, Type, Visibility};
 
+/// Implements `assets::RonAsset` for a struct.
+///
+/// ```ignore
+/// #[derive(Deserialize, Serialize, Clone, Asset, TypePath, RonAsset)]
+/// #[ron_asset(extension = "level.ron", assets = handles)]
+/// struct Level {
+///   #[ron_asset(load = Scene)]
+///   scene: String,
+///   #[ron_asset(load = Font)]
+///   font: Option<String>,
+///   #[serde(skip)]
+///   handles: Option<LevelAssets>,
+/// }
+/// ```
+///
+/// Fields with `load` hold asset paths, which may be wrapped in `Option`, `Vec` or `HashMap`, and
+/// are loaded as nested assets. The handles are stored in a generated `<Name>Assets` struct, in the
+/// field named by `assets`. The generated struct is `Clone`, so the asset can derive the
+/// `Serialize` and `Clone` that `register_ron_asset` needs to save and process it.
+/// Paths are relative to the directory of the document they are written in, see
+/// `assets::resolve_path`, and are saved as written.
+/// Without `extension` the extension is the snake case type name followed by `.ron`.
+/// `#[ron_asset(migrations = MIGRATIONS)]` names a `&[assets::Migration]` constant upgrading older
+/// documents.
+/// `#[ron_asset(settings = LevelSettings)]` sets the per-file `Settings`, `()` by default, and
+/// `#[ron_asset(apply_settings = Level::apply)]` names the
+/// `fn(&mut Self, &Settings) -> Result<(), RonAssetLoaderError>` they are applied with.
 #[proc_macro_derive(RonAsset, attributes(ron_asset))]
 pub fn ron_asset_derive(input: TokenStream) -> TokenStream {
-  let ast = syn::parse(input).unwrap();
-  let parsed = Receiver::from_derive_input(&ast).unwrap();
-  let name = &parsed.ident;
-  let concatenated = format!("_{}Loader", name);
-  let loader_name = syn::Ident::new(&concatenated, name.span());
-  let assets = &parsed.assets;
-  let extension = &parsed.extension;
-
-  let gen = quote! {
-    #[derive(Default)]
-    pub struct #loader_name;
-    impl AssetLoader for #loader_name
-    {
-      type Asset = #name;
-      type Settings = ();
-      type Error = RonAssetLoaderError;
-      fn load<'a>(
-        &'a self,
-        reader: &'a mut Reader,
-        _settings: &'a (),
-        ctx: &'a mut LoadContext,
-      ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
-        Box::pin(async move {
-          let mut bytes = Vec::new();
-          reader.read_to_end(&mut bytes).await?;
-          let ron_asset = from_bytes::<#name>(&bytes)?;
-          let linked_assets = ron_asset.construct_nested_assets(ctx);
-          // TODO: how to access linked_assets
-          Ok(ron_asset)
-        })
-      }
-
-      fn extensions(&self) -> &[&str] {
-        &[#extension]
-      }
-    }
-
-  };
-  gen.into()
+  let ast = syn::parse_macro_input!(input as DeriveInput);
+  match Receiver::from_derive_input(&ast).and_then(|receiver| receiver.expand()) {
+    Ok(tokens) => tokens.into(),
+    Err(error) => error.write_errors().into(),
+  }
 }
 
 #[derive(FromDeriveInput)]
-#[darling(attributes(ron_asset))]
+#[darling(attributes(ron_asset), supports(struct_named))]
 struct Receiver {
   ident: Ident,
-  extension: String,
-  #[darling(with = parse_expr::preserve_str_literal)]
-  assets: Expr,
+  vis: Visibility,
+  generics: syn::Generics,
+  data: Data<Ignored, FieldReceiver>,
+  #[darling(multiple, rename = "extension")]
+  extensions: Vec<LitStr>,
+  /// Field the handles of nested assets are stored in
+  assets: Option<Ident>,
+  migrations: Option<Path>,
+  settings: Option<Path>,
+  apply_settings: Option<Path>,
 }
 
-// #[derive(Default, FromMeta)]
-// #[darling(default)]
-// struct RonAssetParams(syn::LitStr, Option<syn::Ident>);
-// impl Parse for RonAssetParams {
-//   fn parse(input: ParseStream) -> Result<Self> {
-//     let content;
-//     syn::parenthesized!(content in input);
-//     let extension = content.parse()?;
-//     content.parse::<Token![,]>()?;
-//     let Ok(asset_type) = content.parse() else {
-//       return Ok(RonAssetParams(extension, None));
-//     };
-//     Ok(RonAssetParams(extension, Some(asset_type)))
-//   }
-// }
+#[derive(FromField)]
+#[darling(attributes(ron_asset))]
+struct FieldReceiver {
+  ident: Option<Ident>,
+  ty: Type,
+  /// Asset type the path in the field is loaded as
+  load: Option<Path>,
+}
+
+impl Receiver {
+  fn expand(self) -> darling::Result<proc_macro2::TokenStream> {
+    let name = &self.ident;
+    let vis = &self.vis;
+    let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+    let fields = self.data.take_struct().unwrap().fields;
+    let mut errors = darling::Error::accumulator();
 
-// fn impl_ron_asset(ast: &syn::DeriveInput) -> TokenStream {
-//   let attrib = ast
-//     .attrs
-//     .iter()
-//     .filter(|a| a.path().is_ident("ron_asset"))
-//     .nth(0)
-//     .expect("Must have ron asset attrib");
-//   attrib.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated);
-//   let params = syn::parse2(attrib.tts.clone()).expect("Invalid ron_asset attribute");
+    let extensions = if self.extensions.is_empty() {
+      vec![LitStr::new(
+        &format!("{}.ron", snake_case(&name.to_string())),
+        name.span(),
+      )]
+    } else {
+      self.extensions
+    };
+    for extension in extensions.iter() {
+      if extension.value().is_empty() || extension.value().starts_with('.') {
+        errors.push(
+          darling::Error::custom("extension must not be empty or start with `.`")
+            .with_span(extension),
+        );
+      }
+    }
+
+    let loaded: Vec<_> = fields
+      .iter()
+      .filter_map(|f| Some((f.ident.as_ref()?, &f.ty, f.load.as_ref()?)))
+      .collect();
+    let storage = match &self.assets {
+      Some(assets) => {
+        if !fields.iter().any(|f| f.ident.as_ref() == Some(assets)) {
+          errors.push(
+            darling::Error::custom(format!("`{name}` has no field `{assets}`")).with_span(assets),
+          );
+        }
+        Some(assets)
+      }
+      None => {
+        if let Some((field, ..)) = loaded.first() {
+          errors.push(
+            darling::Error::custom(
+              "loaded handles must be kept, name the field to store them in with \
+               `#[ron_asset(assets = field)]` on the struct",
+            )
+            .with_span(field),
+          );
+        }
+        None
+      }
+    };
+    errors.finish()?;
 
-//   let name = &ast.ident;
-//   let assetName = &ast.ident;
-//   let gen = quote! {
-//       impl RonAsset for #name {
-//           fn hello_macro() {
-//               println!("Hello, Macro! My name is {}!", stringify!(#name));
-//           }
-//       }
-//   };
-//   gen.into()
-// }
+    let extensions = quote! { &[#(#extensions),*] };
+    let migrations = self.migrations.map(|migrations| {
+      quote! {
+        fn migrations() -> &'static [::assets::Migration] {
+          #migrations
+        }
+      }
+    });
+    let settings = match &self.settings {
+      Some(settings) => quote! { #settings },
+      None => quote! { () },
+    };
+    let apply_settings = self.apply_settings.map(|apply| {
+      quote! {
+        fn apply_settings(
+          &mut self,
+          settings: &Self::Settings,
+        ) -> ::std::result::Result<(), ::assets::RonAssetLoaderError> {
+          #apply(self, settings)
+        }
+      }
+    });
+    let Some(storage) = storage else {
+      return Ok(quote! {
+        impl #impl_generics ::assets::RonAsset for #name #ty_generics #where_clause {
+          type NestedAssets = ();
+          type Settings = #settings;
+          fn construct_nested_assets<'a>(
+            &'a mut self,
+            _load_context: &'a mut ::bevy::asset::LoadContext,
+          ) -> ::bevy::utils::BoxedFuture<'a, ::std::result::Result<(), ::assets::RonAssetLoaderError>> {
+            ::std::boxed::Box::pin(async move { ::std::result::Result::Ok(()) })
+          }
+          fn extensions() -> &'static [&'static str] {
+            #extensions
+          }
+          #migrations
+          #apply_settings
+        }
+      });
+    };
+
+    let assets_name = format_ident!("{}Assets", name);
+    let doc = format!("Handles of the nested assets of [`{name}`].");
+    let handle_fields = loaded.iter().map(|(field, ty, asset)| {
+      quote_spanned! {ty.span()=>
+        pub #field: <#ty as ::assets::NestedPath>::Handle<#asset>
+      }
+    });
+    let loads = loaded.iter().map(|(field, ty, asset)| {
+      quote_spanned! {ty.span()=>
+        #field: <#ty as ::assets::NestedPath>::load::<#asset>(&self.#field, load_context)?
+      }
+    });
+    let handle_paths = loaded.iter().map(|(field, ty, asset)| {
+      quote_spanned! {ty.span()=>
+        paths.extend(<#ty as ::assets::NestedPath>::handle_paths::<#asset>(&assets.#field));
+      }
+    });
+    let stored = quote_spanned! {storage.span()=>
+      let stored: &::std::option::Option<#assets_name> = &self.#storage;
+    };
+    let store = quote_spanned! {storage.span()=>
+      self.#storage = ::std::option::Option::Some(assets);
+    };
+    Ok(quote! {
+      #[doc = #doc]
+      #[derive(Clone)]
+      #vis struct #assets_name {
+        #(#handle_fields,)*
+      }
+
+      impl #impl_generics ::assets::RonAsset for #name #ty_generics #where_clause {
+        type NestedAssets = #assets_name;
+        type Settings = #settings;
+        fn construct_nested_assets<'a>(
+          &'a mut self,
+          load_context: &'a mut ::bevy::asset::LoadContext,
+        ) -> ::bevy::utils::BoxedFuture<'a, ::std::result::Result<(), ::assets::RonAssetLoaderError>> {
+          ::std::boxed::Box::pin(async move {
+            let assets = #assets_name {
+              #(#loads,)*
+            };
+            #store
+            ::std::result::Result::Ok(())
+          })
+        }
+        fn extensions() -> &'static [&'static str] {
+          #extensions
+        }
+        #migrations
+        #apply_settings
+        fn nested_paths(&self) -> ::std::vec::Vec<::bevy::asset::AssetPath<'static>> {
+          #stored
+          let mut paths = ::std::vec::Vec::new();
+          if let ::std::option::Option::Some(assets) = stored {
+            #(#handle_paths)*
+          }
+          paths
+        }
+      }
+    })
+  }
+}
+
+/// Snake case of a type name, with runs of capitals as one word like `heck` does, e.g.
+/// `IKController` is `ik_controller`.
+fn snake_case(name: &str) -> String {
+  let chars: Vec<char> = name.chars().collect();
+  let mut snake = String::new();
+  for (i, c) in chars.iter().enumerate() {
+    if c.is_uppercase() && i > 0 {
+      let previous = chars[i - 1];
+      let word_follows = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+      let acronym_ends = previous.is_uppercase() && word_follows;
+      if previous.is_lowercase() || previous.is_numeric() || acronym_ends {
+        snake.push('_');
+      }
+    }
+    snake.extend(c.to_lowercase());
+  }
+  snake
+}
@@ -49,3 +49,11 @@ pub fn inspector_ui(world: &mut World, mut selected_entities: Local<SelectedEnti
       });
     });
 }
+
+pub fn animation_graph_ui(world: &mut World) {
+  let mut egui_context = world
+    .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
+    .single(world)
+    .clone();
+  animation::animation_graph_editor(world, egui_context.get_mut());
+}
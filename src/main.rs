@@ -1,4 +1,5 @@
 use animation::AnimationControllerPlugin;
+use assets::{AssetsPreloaded, Preload, PreloadFailed, PreloadPlugin};
 use bevy::{core_pipeline::experimental::taa::TemporalAntiAliasPlugin, prelude::*};
 
 #[cfg(feature = "debug")]
@@ -29,16 +30,22 @@ fn main() {
       SimulationPlugin,
       HookPlugin,
       AnimationControllerPlugin,
+      PreloadPlugin,
     ))
     .add_systems(
       Startup,
+      (preload, scene::setup_test_scene, camera::setup_camera),
+    )
+    .add_systems(
+      Update,
       (
-        scene::setup_test_scene,
-        player::setup_player,
-        camera::setup_camera,
+        // also when preloading failed, with whatever did load
+        player::setup_player
+          .run_if(on_event::<AssetsPreloaded>().or_else(on_event::<PreloadFailed>())),
+        player::update_player,
+        camera::update_camera,
       ),
-    )
-    .add_systems(Update, (player::update_player, camera::update_camera));
+    );
 
   #[cfg(feature = "debug")]
   app
@@ -49,8 +56,13 @@ fn main() {
     ))
     .add_systems(
       Update,
-      debug::inspector_ui.run_if(input_toggle_active(true, KeyCode::Escape)),
+      (debug::inspector_ui, debug::animation_graph_ui)
+        .run_if(input_toggle_active(true, KeyCode::Escape)),
     );
 
   app.run();
 }
+
+fn preload(mut cmd: Commands, asset_server: Res<AssetServer>) {
+  cmd.insert_resource(Preload::new(asset_server.load("game.manifest.ron")));
+}
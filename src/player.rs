@@ -8,7 +8,7 @@ pub fn setup_player(mut cmd: Commands, asset_server: Res<AssetServer>) {
   let controller = asset_server.load::<BasicAnimationController>("player.basic.anim.ron");
   cmd
     .spawn(SceneBundle {
-      scene: asset_server.load("char.glb#Scene0"),
+      scene: asset_server.load("char.gltf#Scene0"),
       ..default()
     })
     .insert((Name::new("Player"), Player))